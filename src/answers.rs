@@ -0,0 +1,388 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::inputs::Checksum;
+use crate::table::format_table;
+
+// One submitted answer and what the puzzle said about it. Each line of the answers file is
+// `day part input response answer [checksum]`, where input `-` is the plain dayN.txt and the checksum of the
+// input is only kept for correct answers, e.g.
+// 3 2 - too-low 69779216
+// 3 2 - correct 78683433 82aed2bad35a7ed4
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Guess {
+  pub day: u8,
+  pub part: u8,
+  pub input: Option<String>,
+  pub response: Response,
+  pub answer: String,
+  pub checksum: Option<Checksum>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Response {
+  Correct,
+  TooLow,
+  TooHigh,
+  Wrong,
+}
+
+impl FromStr for Response {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "correct" => Ok(Response::Correct),
+      "too-low" => Ok(Response::TooLow),
+      "too-high" => Ok(Response::TooHigh),
+      "wrong" => Ok(Response::Wrong),
+      _ => Err(()),
+    }
+  }
+}
+
+impl fmt::Display for Response {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let name = match self {
+      Response::Correct => "correct",
+      Response::TooLow => "too-low",
+      Response::TooHigh => "too-high",
+      Response::Wrong => "wrong",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+impl Guess {
+  // Every field is written as one whitespace separated word, so anything that would split it, read as the
+  // `-` placeholder or start a `#` comment is rejected here rather than breaking the answers file
+  pub fn new(
+    day: u8,
+    part: u8,
+    input: Option<String>,
+    response: Response,
+    answer: String,
+    checksum: Option<Checksum>,
+  ) -> Result<Guess, &'static str> {
+    let is_field = |text: &str| !text.is_empty() && !text.contains(|c: char| c.is_whitespace() || c == '#');
+    if !is_field(&answer) {
+      return Err("an answer must be one word without `#`");
+    }
+    if input.as_deref().is_some_and(|input| input == "-" || !is_field(input)) {
+      return Err("an input name must be one word without `#` and not `-`");
+    }
+
+    Ok(Guess { day, part, input, response, answer, checksum })
+  }
+}
+
+impl fmt::Display for Guess {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{} {} {} {} {}",
+      self.day,
+      self.part,
+      self.input.as_deref().unwrap_or("-"),
+      self.response,
+      self.answer
+    )?;
+    if let Some(checksum) = self.checksum {
+      write!(f, " {}", checksum)?;
+    }
+    Ok(())
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnswersError {
+  pub line: usize,
+  pub text: String,
+  pub problem: &'static str,
+}
+
+impl fmt::Display for AnswersError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "answers line {}: {} in `{}`", self.line, self.problem, self.text)
+  }
+}
+
+impl Error for AnswersError {}
+
+// How a solver's answer compares with what has been submitted before
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Check {
+  Correct,
+  Mismatch { expected: String },
+  InputChanged { recorded: Checksum },
+  // The answer was already rejected, or is on the wrong side of a too-low or too-high guess
+  Rejected { response: Response, guess: String },
+  Unrecorded,
+}
+
+impl Check {
+  pub fn is_failure(&self) -> bool {
+    !matches!(self, Check::Correct | Check::Unrecorded)
+  }
+}
+
+impl fmt::Display for Check {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Check::Correct => write!(f, "ok"),
+      Check::Mismatch { expected } => write!(f, "MISMATCH, expected {}", expected),
+      Check::InputChanged { recorded } => write!(f, "INPUT CHANGED, answer was recorded for {}", recorded),
+      Check::Rejected { response: Response::TooLow, guess } => write!(f, "REJECTED, {} was already too low", guess),
+      Check::Rejected { response: Response::TooHigh, guess } => write!(f, "REJECTED, {} was already too high", guess),
+      Check::Rejected { guess, .. } => write!(f, "REJECTED, {} was already wrong", guess),
+      Check::Unrecorded => write!(f, "unrecorded"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Answers {
+  pub guesses: Vec<Guess>,
+}
+
+impl Answers {
+  // Blank lines and `#` comments are skipped
+  pub fn parse(text: &str) -> Result<Answers, AnswersError> {
+    let mut guesses = Vec::new();
+
+    for (line_index, line) in text.lines().enumerate() {
+      let content = line.split('#').next().unwrap_or("").trim();
+      if content.is_empty() {
+        continue;
+      }
+      let error = |problem| AnswersError {
+        line: line_index + 1,
+        text: line.to_string(),
+        problem,
+      };
+
+      let fields = content.split_whitespace().collect::<Vec<_>>();
+      let [day, part, input, response, answer, rest @ ..] = fields.as_slice() else {
+        return Err(error("expected `day part input response answer`"));
+      };
+      let checksum = match rest {
+        [] => None,
+        [checksum] => Some(Checksum(u64::from_str_radix(checksum, 16).map_err(|_| error("invalid checksum"))?)),
+        _ => return Err(error("unexpected field")),
+      };
+
+      guesses.push(Guess {
+        day: day.parse().map_err(|_| error("invalid day"))?,
+        part: part.parse().map_err(|_| error("invalid part"))?,
+        input: (*input != "-").then(|| input.to_string()),
+        response: response.parse().map_err(|_| error("response must be correct, too-low, too-high or wrong"))?,
+        answer: answer.to_string(),
+        checksum,
+      });
+    }
+
+    Ok(Answers { guesses })
+  }
+
+  pub fn for_input<'a>(&'a self, day: u8, part: u8, input: Option<&'a str>) -> impl Iterator<Item = &'a Guess> + 'a {
+    self
+      .guesses
+      .iter()
+      .filter(move |guess| guess.day == day && guess.part == part && guess.input.as_deref() == input)
+  }
+
+  pub fn check(&self, day: u8, part: u8, input: Option<&str>, answer: &str, checksum: Checksum) -> Check {
+    if let Some(correct) = self.for_input(day, part, input).find(|guess| guess.response == Response::Correct) {
+      return match correct.checksum {
+        Some(recorded) if recorded != checksum => Check::InputChanged { recorded },
+        _ if correct.answer == answer => Check::Correct,
+        _ => Check::Mismatch {
+          expected: correct.answer.clone(),
+        },
+      };
+    }
+
+    let value = answer.parse::<i64>().ok();
+    let rejected = self.for_input(day, part, input).find(|guess| {
+      let bound = guess.answer.parse::<i64>().ok();
+      guess.answer == answer
+        || match (guess.response, value, bound) {
+          (Response::TooLow, Some(value), Some(bound)) => value <= bound,
+          (Response::TooHigh, Some(value), Some(bound)) => value >= bound,
+          _ => false,
+        }
+    });
+
+    match rejected {
+      Some(guess) => Check::Rejected {
+        response: guess.response,
+        guess: guess.answer.clone(),
+      },
+      None => Check::Unrecorded,
+    }
+  }
+}
+
+// One row of `verify` output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+  pub day: u8,
+  pub part: u8,
+  pub solver: &'static str,
+  pub input: Option<String>,
+  pub actual: Result<String, String>,
+  pub check: Check,
+}
+
+impl Verification {
+  pub fn is_failure(&self) -> bool {
+    self.actual.is_err() || self.check.is_failure()
+  }
+}
+
+pub fn verification_table(verifications: &[Verification]) -> String {
+  let rows = verifications
+    .iter()
+    .map(|verification| {
+      let (actual, status) = match &verification.actual {
+        Ok(actual) => (actual.clone(), verification.check.to_string()),
+        Err(error) => (String::from("-"), format!("ERROR {}", error)),
+      };
+      vec![
+        verification.day.to_string(),
+        verification.part.to_string(),
+        verification.solver.to_string(),
+        verification.input.clone().unwrap_or_else(|| String::from("-")),
+        actual,
+        status,
+      ]
+    })
+    .collect::<Vec<_>>();
+
+  format_table(&["day", "part", "solver", "input", "answer", "status"], &rows)
+}
+
+#[cfg(test)]
+mod answers_tests {
+  use super::*;
+  use proptest::prelude::*;
+
+  const ANSWERS: &str = "# day part input response answer checksum
+3 2 - too-low 69779216
+3 2 - too-high 83596387
+3 2 - correct 78683433 00000000000000ff
+
+3 1 example correct 161 # no checksum, always trusted
+4 1 - wrong 2000
+";
+
+  #[test]
+  fn parses_guesses_and_round_trips_them() {
+    let answers = Answers::parse(ANSWERS).unwrap();
+
+    assert_eq!(answers.guesses.len(), 5);
+    assert_eq!(answers.guesses[2].checksum, Some(Checksum(255)));
+    assert_eq!(answers.guesses[3].input.as_deref(), Some("example"));
+    assert_eq!(answers.guesses[2].to_string(), "3 2 - correct 78683433 00000000000000ff");
+    assert_eq!(Answers::parse(&answers.guesses[0].to_string()).unwrap().guesses[0], answers.guesses[0]);
+  }
+
+  #[test]
+  fn new_rejects_answers_the_file_cannot_hold() {
+    let guess = |input: Option<&str>, answer: &str| {
+      Guess::new(2, 2, input.map(str::to_string), Response::Correct, answer.to_string(), Some(Checksum(1)))
+    };
+
+    assert!(guess(None, "531").is_ok());
+    assert!(guess(Some("example"), "4").is_ok());
+    assert_eq!(guess(None, "k=0: 2, k=1: 4"), Err("an answer must be one word without `#`"));
+    assert_eq!(guess(None, "#4"), Err("an answer must be one word without `#`"));
+    assert_eq!(guess(None, ""), Err("an answer must be one word without `#`"));
+    assert_eq!(guess(Some("-"), "4"), Err("an input name must be one word without `#` and not `-`"));
+  }
+
+  proptest! {
+    #[test]
+    fn every_new_guess_parses_back(
+      input in proptest::option::of("[a-z#\\- ]{0,4}"),
+      answer in "[0-9a-z#:=, \t-]{0,6}",
+      correct in any::<bool>(),
+    ) {
+      let (response, checksum) = if correct { (Response::Correct, Some(Checksum(7))) } else { (Response::TooLow, None) };
+
+      if let Ok(guess) = Guess::new(3, 1, input, response, answer, checksum) {
+        prop_assert_eq!(Answers::parse(&guess.to_string()), Ok(Answers { guesses: vec![guess] }));
+      }
+    }
+  }
+
+  #[test]
+  fn invalid_lines_report_the_line_number() {
+    let error = Answers::parse("3 2 - correct 1\n3 2 - maybe 5").unwrap_err();
+
+    assert_eq!(error.line, 2);
+    assert_eq!(error.problem, "response must be correct, too-low, too-high or wrong");
+  }
+
+  #[test]
+  fn checks_against_the_correct_answer() {
+    let answers = Answers::parse(ANSWERS).unwrap();
+
+    assert_eq!(answers.check(3, 2, None, "78683433", Checksum(255)), Check::Correct);
+    assert_eq!(
+      answers.check(3, 2, None, "78683434", Checksum(255)),
+      Check::Mismatch { expected: String::from("78683433") }
+    );
+    assert_eq!(answers.check(3, 2, None, "78683433", Checksum(1)), Check::InputChanged { recorded: Checksum(255) });
+    assert_eq!(answers.check(3, 1, Some("example"), "161", Checksum(1)), Check::Correct);
+  }
+
+  #[test]
+  fn hints_reject_answers_without_a_correct_one() {
+    let answers = Answers::parse("3 2 - too-low 69779216\n3 2 - too-high 83596387\n4 1 - wrong 2000").unwrap();
+
+    assert_eq!(
+      answers.check(3, 2, None, "69000000", Checksum(0)),
+      Check::Rejected { response: Response::TooLow, guess: String::from("69779216") }
+    );
+    assert_eq!(
+      answers.check(3, 2, None, "83596387", Checksum(0)),
+      Check::Rejected { response: Response::TooHigh, guess: String::from("83596387") }
+    );
+    assert_eq!(answers.check(3, 2, None, "78683433", Checksum(0)), Check::Unrecorded);
+    assert!(answers.check(4, 1, None, "2000", Checksum(0)).is_failure());
+    assert_eq!(answers.check(4, 1, Some("example"), "2000", Checksum(0)), Check::Unrecorded);
+  }
+
+  #[test]
+  fn table_lines_up_columns() {
+    let verifications = [
+      Verification {
+        day: 3,
+        part: 2,
+        solver: "Chars",
+        input: None,
+        actual: Ok(String::from("78683433")),
+        check: Check::Correct,
+      },
+      Verification {
+        day: 5,
+        part: 1,
+        solver: "Chars",
+        input: Some(String::from("example")),
+        actual: Ok(String::from("142")),
+        check: Check::Mismatch { expected: String::from("143") },
+      },
+    ];
+
+    assert_eq!(
+      verification_table(&verifications),
+      "\
+day | part | solver | input   | answer   | status
+3   | 2    | Chars  | -       | 78683433 | ok
+5   | 1    | Chars  | example | 142      | MISMATCH, expected 143
+"
+    );
+  }
+}
@@ -0,0 +1,334 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::solvers::Solver;
+use crate::table::format_table;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+// The system allocator, counting every allocation. A binary opts in with
+// #[global_allocator] static ALLOCATOR: CountingAllocator = CountingAllocator;
+// Without it every benchmark reports 0 allocations.
+pub struct CountingAllocator;
+
+fn count(size: usize) {
+  ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+  ALLOCATED_BYTES.fetch_add(u64::try_from(size).unwrap_or(u64::MAX), Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    count(layout.size());
+    System.alloc(layout)
+  }
+
+  unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+    count(layout.size());
+    System.alloc_zeroed(layout)
+  }
+
+  unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    count(new_size);
+    System.realloc(ptr, layout, new_size)
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    System.dealloc(ptr, layout)
+  }
+}
+
+// (allocations, bytes) since the process started
+fn allocation_counts() -> (u64, u64) {
+  (ALLOCATIONS.load(Ordering::Relaxed), ALLOCATED_BYTES.load(Ordering::Relaxed))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Benchmark {
+  pub day: u8,
+  pub part: u8,
+  pub solver: String,
+  pub input: Option<String>,
+  pub iterations: u32,
+  // Median time of the day's generator. min, median and p95 time the part alone, on input that is already parsed.
+  pub parse: Duration,
+  pub min: Duration,
+  pub median: Duration,
+  pub p95: Duration,
+  // Per run. The fewest seen over all iterations, so a stray allocation from another thread doesn't count.
+  pub allocations: u64,
+  pub allocated_bytes: u64,
+}
+
+impl Benchmark {
+  fn key(&self) -> (u8, u8, &str, Option<&str>) {
+    (self.day, self.part, &self.solver, self.input.as_deref())
+  }
+}
+
+// One untimed warm-up run, which also surfaces a failing solver before any timing starts
+pub fn run(solver: &Solver, input_name: Option<&str>, input: &str, iterations: u32) -> Result<Benchmark, String> {
+  if iterations == 0 {
+    return Err(String::from("a benchmark needs at least one iteration"));
+  }
+  let parsed = solver.parse(input).map_err(|e| e.to_string())?;
+  (solver.runner)(input, &*parsed).map_err(|e| e.to_string())?;
+
+  let mut parse_times = Vec::with_capacity(iterations as usize);
+  for _ in 0..iterations {
+    let start = Instant::now();
+    let reparsed = solver.parse(input);
+    parse_times.push(start.elapsed());
+    drop(reparsed);
+  }
+  parse_times.sort();
+
+  let mut times = Vec::with_capacity(iterations as usize);
+  let (mut allocations, mut allocated_bytes) = (u64::MAX, u64::MAX);
+  for _ in 0..iterations {
+    let (allocations_before, bytes_before) = allocation_counts();
+    let start = Instant::now();
+    let result = (solver.runner)(input, &*parsed);
+    let elapsed = start.elapsed();
+    let (allocations_after, bytes_after) = allocation_counts();
+    drop(result);
+
+    times.push(elapsed);
+    allocations = allocations.min(allocations_after - allocations_before);
+    allocated_bytes = allocated_bytes.min(bytes_after - bytes_before);
+  }
+  times.sort();
+
+  Ok(Benchmark {
+    day: solver.day,
+    part: solver.part,
+    solver: solver.name.to_string(),
+    input: input_name.map(str::to_string),
+    iterations,
+    parse: percentile(&parse_times, 50),
+    min: times[0],
+    median: percentile(&times, 50),
+    p95: percentile(&times, 95),
+    allocations,
+    allocated_bytes,
+  })
+}
+
+// Nearest-rank percentile of sorted, non-empty times
+fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+  let rank = (sorted.len() * percent).div_ceil(100).max(1);
+  sorted[rank - 1]
+}
+
+// One benchmark per line so baselines diff nicely in git. Solver and input names never contain quotes or commas.
+pub fn to_json(benchmarks: &[Benchmark]) -> String {
+  let lines = benchmarks
+    .iter()
+    .map(|benchmark| {
+      format!(
+        "{{\"day\":{},\"part\":{},\"solver\":\"{}\",\"input\":{},\"iterations\":{},\"parse_ns\":{},\"min_ns\":{},\"median_ns\":{},\"p95_ns\":{},\"allocations\":{},\"allocated_bytes\":{}}}",
+        benchmark.day,
+        benchmark.part,
+        benchmark.solver,
+        benchmark.input.as_ref().map(|input| format!("\"{}\"", input)).unwrap_or_else(|| String::from("null")),
+        benchmark.iterations,
+        benchmark.parse.as_nanos(),
+        benchmark.min.as_nanos(),
+        benchmark.median.as_nanos(),
+        benchmark.p95.as_nanos(),
+        benchmark.allocations,
+        benchmark.allocated_bytes,
+      )
+    })
+    .collect::<Vec<String>>();
+
+  format!("[{}]\n", lines.join(",\n"))
+}
+
+// Reads back what to_json wrote, not JSON in general
+pub fn from_json(json: &str) -> Result<Vec<Benchmark>, String> {
+  let mut benchmarks = Vec::new();
+
+  for (line_index, line) in json.lines().enumerate() {
+    let object = line.trim().trim_start_matches('[').trim_end_matches(']').trim_end_matches(',');
+    if object.is_empty() {
+      continue;
+    }
+    let invalid = |problem: &str| format!("baseline line {}: {} in `{}`", line_index + 1, problem, line);
+    let fields = object
+      .strip_prefix('{')
+      .and_then(|object| object.strip_suffix('}'))
+      .ok_or_else(|| invalid("expected an object"))?
+      .split(',')
+      .map(|field| {
+        let (key, value) = field.split_once(':').ok_or_else(|| invalid("expected `\"key\":value`"))?;
+        Ok((key.trim_matches('"'), value))
+      })
+      .collect::<Result<Vec<_>, String>>()?;
+
+    let field = |name: &str| {
+      fields
+        .iter()
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| *value)
+        .ok_or_else(|| invalid(&format!("missing `{}`", name)))
+    };
+    let number = |name: &str| field(name)?.parse::<u64>().map_err(|_| invalid(&format!("invalid `{}`", name)));
+    let nanos = |name: &str| number(name).map(Duration::from_nanos);
+    let string = |name: &str| field(name).map(|value| value.trim_matches('"').to_string());
+
+    benchmarks.push(Benchmark {
+      day: u8::try_from(number("day")?).map_err(|_| invalid("invalid `day`"))?,
+      part: u8::try_from(number("part")?).map_err(|_| invalid("invalid `part`"))?,
+      solver: string("solver")?,
+      input: Some(field("input")?).filter(|input| *input != "null").map(|input| input.trim_matches('"').to_string()),
+      iterations: u32::try_from(number("iterations")?).map_err(|_| invalid("invalid `iterations`"))?,
+      parse: nanos("parse_ns")?,
+      min: nanos("min_ns")?,
+      median: nanos("median_ns")?,
+      p95: nanos("p95_ns")?,
+      allocations: number("allocations")?,
+      allocated_bytes: number("allocated_bytes")?,
+    });
+  }
+
+  Ok(benchmarks)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+  pub benchmark: Benchmark,
+  pub baseline_median: Option<Duration>,
+  // Relative change of the median, 0.25 is 25% slower
+  pub change: Option<f64>,
+  pub regression: bool,
+}
+
+// Medians are compared because min is too optimistic and p95 too noisy for a short run.
+// A benchmark without a baseline entry is never a regression.
+pub fn compare(benchmarks: &[Benchmark], baseline: &[Benchmark], threshold: f64) -> Vec<Comparison> {
+  benchmarks
+    .iter()
+    .map(|benchmark| {
+      let baseline_median = baseline.iter().find(|old| old.key() == benchmark.key()).map(|old| old.median);
+      let change = baseline_median
+        .filter(|median| !median.is_zero())
+        .map(|median| benchmark.median.as_nanos() as f64 / median.as_nanos() as f64 - 1.0);
+
+      Comparison {
+        benchmark: benchmark.clone(),
+        baseline_median,
+        change,
+        regression: change.is_some_and(|change| change > threshold),
+      }
+    })
+    .collect()
+}
+
+pub fn comparison_table(comparisons: &[Comparison]) -> String {
+  let rows = comparisons
+    .iter()
+    .map(|comparison| {
+      let benchmark = &comparison.benchmark;
+      let change = match (comparison.change, comparison.regression) {
+        (Some(change), true) => format!("{:+.1}% SLOWER", change * 100.0),
+        (Some(change), false) => format!("{:+.1}%", change * 100.0),
+        (None, _) => String::from("-"),
+      };
+      vec![
+        benchmark.day.to_string(),
+        benchmark.part.to_string(),
+        benchmark.solver.clone(),
+        benchmark.input.clone().unwrap_or_else(|| String::from("-")),
+        format!("{:?}", benchmark.parse),
+        format!("{:?}", benchmark.min),
+        format!("{:?}", benchmark.median),
+        format!("{:?}", benchmark.p95),
+        benchmark.allocations.to_string(),
+        benchmark.allocated_bytes.to_string(),
+        change,
+      ]
+    })
+    .collect::<Vec<_>>();
+
+  format_table(
+    &["day", "part", "solver", "input", "parse", "min", "median", "p95", "allocs", "bytes", "vs baseline"],
+    &rows,
+  )
+}
+
+#[cfg(test)]
+mod bench_tests {
+  use super::*;
+  use crate::solvers::select;
+
+  fn benchmark(solver: &str, median_micros: u64) -> Benchmark {
+    Benchmark {
+      day: 2,
+      part: 2,
+      solver: solver.to_string(),
+      input: None,
+      iterations: 10,
+      parse: Duration::from_micros(7),
+      min: Duration::from_micros(median_micros - 1),
+      median: Duration::from_micros(median_micros),
+      p95: Duration::from_micros(median_micros + 5),
+      allocations: 3,
+      allocated_bytes: 96,
+    }
+  }
+
+  #[test]
+  fn percentiles_use_the_nearest_rank() {
+    let times = (1..=20).map(Duration::from_millis).collect::<Vec<_>>();
+
+    assert_eq!(percentile(&times, 50), Duration::from_millis(10));
+    assert_eq!(percentile(&times, 95), Duration::from_millis(19));
+    assert_eq!(percentile(&times[..1], 95), Duration::from_millis(1));
+  }
+
+  #[test]
+  fn runs_a_solver() {
+    let solver = select(Some(3), Some(1)).next().unwrap();
+
+    let benchmark = run(solver, Some("example"), "mul(2,4)", 5).unwrap();
+
+    assert_eq!((benchmark.day, benchmark.part, benchmark.solver.as_str()), (3, 1, "Chars"));
+    assert_eq!(benchmark.input.as_deref(), Some("example"));
+    assert!(benchmark.min <= benchmark.median && benchmark.median <= benchmark.p95);
+    assert!(run(solver, None, "", 0).is_err());
+  }
+
+  #[test]
+  fn json_round_trips() {
+    let mut named = benchmark("ToleranceCurve", 40);
+    named.input = Some(String::from("example"));
+    let benchmarks = vec![benchmark("Chars", 20), named];
+
+    let json = to_json(&benchmarks);
+
+    assert!(json.starts_with("[{\"day\":2,\"part\":2,\"solver\":\"Chars\",\"input\":null,\"iterations\":10,\"parse_ns\":7000,\"min_ns\":19000,"));
+    assert_eq!(from_json(&json), Ok(benchmarks));
+  }
+
+  #[test]
+  fn invalid_baselines_report_the_line() {
+    let error = from_json("[{\"day\":2}]").unwrap_err();
+
+    assert_eq!(error, "baseline line 1: missing `part` in `[{\"day\":2}]`");
+  }
+
+  #[test]
+  fn flags_slowdowns_beyond_the_threshold() {
+    let baseline = vec![benchmark("Chars", 100), benchmark("ToleranceCurve", 100)];
+    let current = vec![benchmark("Chars", 105), benchmark("ToleranceCurve", 150), benchmark("Brute", 10)];
+
+    let comparisons = compare(&current, &baseline, 0.10);
+
+    assert_eq!(comparisons.iter().map(|comparison| comparison.regression).collect::<Vec<_>>(), vec![false, true, false]);
+    assert_eq!(comparisons[1].change, Some(0.5));
+    assert_eq!(comparisons[2].baseline_median, None);
+    assert!(comparison_table(&comparisons).contains("| +50.0% SLOWER\n"));
+  }
+}
@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use crate::bench;
+use crate::solvers::Solver;
+use crate::table::format_table;
+
+// One variant's answer and its median time. Parsing is included because variants don't always share a generator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariantRun {
+  pub solver: &'static str,
+  pub answer: Result<String, String>,
+  pub time: Option<Duration>,
+}
+
+// Every variant of one part run on the same input. The first run is the reference the others have to agree with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossCheck {
+  pub day: u8,
+  pub part: u8,
+  pub input: Option<String>,
+  pub runs: Vec<VariantRun>,
+}
+
+impl CrossCheck {
+  // `variants` are the solvers of one part, as grouped by solvers::variants. A variant that fails isn't timed.
+  pub fn run(variants: &[&Solver], input_name: Option<&str>, input: &str, iterations: u32) -> CrossCheck {
+    let runs = variants
+      .iter()
+      .map(|solver| {
+        let answer = solver.solve(input).map_err(|e| e.to_string());
+        let time = match answer {
+          Ok(_) => bench::run(solver, input_name, input, iterations).ok().map(|benchmark| benchmark.parse + benchmark.median),
+          Err(_) => None,
+        };
+        VariantRun { solver: solver.name, answer, time }
+      })
+      .collect();
+
+    CrossCheck {
+      day: variants[0].day,
+      part: variants[0].part,
+      input: input_name.map(str::to_string),
+      runs,
+    }
+  }
+
+  pub fn agrees(&self) -> bool {
+    self.runs.iter().all(|run| run.answer.is_ok() && run.answer == self.runs[0].answer)
+  }
+}
+
+pub fn cross_check_table(checks: &[CrossCheck]) -> String {
+  let mut rows = Vec::new();
+  for check in checks {
+    let fastest = check.runs.iter().filter_map(|run| run.time).min();
+    let reference = &check.runs[0];
+    for (index, run) in check.runs.iter().enumerate() {
+      let (answer, status) = match &run.answer {
+        Err(error) => (String::from("-"), format!("ERROR {}", error)),
+        Ok(answer) if index == 0 => (answer.clone(), String::from("reference")),
+        Ok(answer) if run.answer == reference.answer => (answer.clone(), String::from("agrees")),
+        Ok(answer) => (answer.clone(), format!("DISAGREES with {}", reference.solver)),
+      };
+      let relative = match (run.time, fastest) {
+        (Some(time), Some(fastest)) if !fastest.is_zero() => format!("{:.2}x", time.as_nanos() as f64 / fastest.as_nanos() as f64),
+        _ => String::from("-"),
+      };
+      rows.push(vec![
+        check.day.to_string(),
+        check.part.to_string(),
+        run.solver.to_string(),
+        check.input.clone().unwrap_or_else(|| String::from("-")),
+        answer,
+        run.time.map_or_else(|| String::from("-"), |time| format!("{:?}", time)),
+        relative,
+        status,
+      ]);
+    }
+  }
+
+  format_table(&["day", "part", "solver", "input", "answer", "time", "vs fastest", "status"], &rows)
+}
+
+#[cfg(test)]
+mod cross_check_tests {
+  use super::*;
+  use crate::solvers::variants;
+
+  fn run(solver: &'static str, answer: Result<&str, &str>, micros: Option<u64>) -> VariantRun {
+    VariantRun {
+      solver,
+      answer: answer.map(str::to_string).map_err(str::to_string),
+      time: micros.map(Duration::from_micros),
+    }
+  }
+
+  #[test]
+  fn runs_every_variant_on_the_same_input() {
+    let groups = variants(Some(3), Some(2));
+
+    let check = CrossCheck::run(&groups[0], Some("example"), "mul(2,4)don't()mul(5,5)do()mul(8,5)", 3);
+
+    assert!(check.agrees());
+    assert_eq!(check.runs.iter().map(|run| run.solver).collect::<Vec<_>>(), vec!["Chars", "Regex"]);
+    assert!(check.runs.iter().all(|run| run.answer == Ok(String::from("48")) && run.time.is_some()));
+  }
+
+  #[test]
+  fn failures_and_different_answers_disagree() {
+    let check = |runs| CrossCheck { day: 5, part: 2, input: None, runs };
+
+    assert!(check(vec![run("Chars", Ok("123"), Some(10)), run("SwapSort", Ok("123"), Some(30))]).agrees());
+    assert!(!check(vec![run("Chars", Ok("123"), Some(10)), run("SwapSort", Ok("124"), Some(30))]).agrees());
+    assert!(!check(vec![run("Chars", Err("cycle"), None), run("SwapSort", Err("cycle"), None)]).agrees());
+  }
+
+  #[test]
+  fn table_shows_speed_relative_to_the_fastest() {
+    let checks = [CrossCheck {
+      day: 5,
+      part: 2,
+      input: None,
+      runs: vec![run("Chars", Ok("123"), Some(10)), run("SwapSort", Ok("124"), Some(35)), run("Broken", Err("oops"), None)],
+    }];
+
+    assert_eq!(
+      cross_check_table(&checks),
+      "\
+day | part | solver   | input | answer | time | vs fastest | status
+5   | 2    | Chars    | -     | 123    | 10µs | 1.00x      | reference
+5   | 2    | SwapSort | -     | 124    | 35µs | 3.50x      | DISAGREES with Chars
+5   | 2    | Broken   | -     | -      | -    | -          | ERROR oops
+"
+    );
+  }
+}
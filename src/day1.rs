@@ -40,52 +40,129 @@ Your actual left and right lists contain many location IDs. What is the total di
 
 use std::collections::HashMap;
 
-#[aoc(day1, part1, Chars)]
-pub fn part1(input: &str) -> i32 {
-  let (left_sorted_number_arr, right_sorted_number_arr) = get_sorted_arrays(input);
+use crate::parse_error::{parse_number, ParseError, ParseErrorKind};
+use crate::solve_error::{checked_sum, Overflow};
+
+const DAY: u8 = 1;
+
+// Both lists sorted, so the nth smallest left location pairs up with the nth smallest right one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocationLists {
+  pub left: Vec<i32>,
+  pub right: Vec<i32>,
+}
 
-  let mut difference_sum = 0;
-  for (pos, left_item) in left_sorted_number_arr.iter().enumerate() {
-    let right_item = right_sorted_number_arr[pos];
-    difference_sum += (left_item - right_item).abs();
+#[aoc_generator(day1)]
+pub fn parse_location_lists(input: &str) -> Result<LocationLists, ParseError> {
+  let lines = input.lines();
+  let mut left_sorted_number_arr: Vec<i32> = Vec::new();
+  let mut right_sorted_number_arr: Vec<i32> = Vec::new();
+  for (line_index, line) in lines.enumerate() {
+    let line_number = line_index + 1;
+    let mut one_line_split = line.split("   ");
+    let left_item = one_line_split.next().unwrap_or(line);
+    let Some(right_item) = one_line_split.next() else {
+      return Err(ParseError::new(DAY, line_number, 1, line, ParseErrorKind::MissingSeparator("   ")));
+    };
+    if let Some(extra_item) = one_line_split.next() {
+      return Err(ParseError::at_token(DAY, line_number, line, extra_item, ParseErrorKind::UnexpectedField));
+    }
+
+    left_sorted_number_arr.push(parse_number(DAY, line_number, line, left_item)?);
+    right_sorted_number_arr.push(parse_number(DAY, line_number, line, right_item)?);
   }
 
-  difference_sum
+  left_sorted_number_arr.sort();
+  right_sorted_number_arr.sort();
+
+  Ok(LocationLists {
+    left: left_sorted_number_arr,
+    right: right_sorted_number_arr,
+  })
 }
 
-#[aoc(day1, part2, Chars)]
-pub fn part2(input: &str) -> i32 {
-  let (left_sorted_number_arr, right_sorted_number_arr) = get_sorted_arrays(input);
+#[aoc(day1, part1, Chars)]
+pub fn part1(lists: &LocationLists) -> Result<i64, Overflow> {
+  // the difference of two i32s always fits in an i64, only the sum can overflow
+  let differences = lists
+    .left
+    .iter()
+    .zip(lists.right.iter())
+    .map(|(left_item, right_item)| (i64::from(*left_item) - i64::from(*right_item)).abs());
+
+  checked_sum(DAY, "sum of distances", differences)
+}
 
-  let mut right_number_occurrence_count: HashMap<i32, i32> = HashMap::new();
-  for right_item in right_sorted_number_arr.iter() {
+#[aoc(day1, part2, Chars)]
+pub fn part2(lists: &LocationLists) -> Result<i64, Overflow> {
+  let mut right_number_occurrence_count: HashMap<i32, i64> = HashMap::new();
+  for right_item in lists.right.iter() {
     let count = right_number_occurrence_count.entry(*right_item).or_insert(0);
     *count += 1;
   }
 
-  let mut similarity_score = 0;
-  for (_, left_item) in left_sorted_number_arr.iter().enumerate() {
-    similarity_score += left_item * right_number_occurrence_count.get(left_item).unwrap_or(&0i32);
+  let mut similarity_score = 0i64;
+  for left_item in lists.left.iter() {
+    let count = right_number_occurrence_count.get(left_item).unwrap_or(&0);
+    similarity_score = i64::from(*left_item)
+      .checked_mul(*count)
+      .and_then(|score| similarity_score.checked_add(score))
+      .ok_or(Overflow::new(DAY, "similarity score"))?;
   }
 
-  similarity_score
+  Ok(similarity_score)
 }
 
-fn get_sorted_arrays(input: &str) -> (Vec<i32>, Vec<i32>) {
-  let lines = input.lines();
-  let mut left_arr: Vec<&str> = Vec::new();
-  let mut right_arr: Vec<&str> = Vec::new();
-  for line in lines {
-    let one_line_split = line.split("   ");
-    let collected_items = one_line_split.collect::<Vec<&str>>();
-    left_arr.push(collected_items[0]);
-    right_arr.push(collected_items[1]);
+#[cfg(test)]
+mod parse_location_lists_tests {
+  use super::*;
+
+  #[test]
+  fn provided_example_is_sorted() {
+    let lists = parse_location_lists("3   4\n4   3\n2   5\n1   3\n3   9\n3   3").unwrap();
+
+    assert_eq!(lists.left, vec![1, 2, 3, 3, 3, 4]);
+    assert_eq!(lists.right, vec![3, 3, 3, 4, 5, 9]);
   }
 
-  let mut left_sorted_number_arr = left_arr.iter().map(|item| item.parse::<i32>().unwrap()).collect::<Vec<i32>>();
-  left_sorted_number_arr.sort();
-  let mut right_sorted_number_arr: Vec<i32> = right_arr.iter().map(|item| item.parse::<i32>().unwrap()).collect::<Vec<i32>>();
-  right_sorted_number_arr.sort();
+  #[test]
+  fn invalid_number_reports_line_and_column() {
+    let error = parse_location_lists("3   4\n4   x3").unwrap_err();
+
+    assert_eq!(error, ParseError::new(DAY, 2, 5, "x3", ParseErrorKind::InvalidNumber));
+  }
+
+  #[test]
+  fn missing_separator_is_an_error() {
+    let error = parse_location_lists("3 4").unwrap_err();
+
+    assert_eq!(error.kind, ParseErrorKind::MissingSeparator("   "));
+    assert_eq!(error.line, 1);
+  }
+}
+#[cfg(test)]
+mod part_tests {
+  use super::*;
+
+  #[test]
+  fn provided_example() {
+    let lists = parse_location_lists("3   4\n4   3\n2   5\n1   3\n3   9\n3   3").unwrap();
 
-  (left_sorted_number_arr, right_sorted_number_arr)
-}
\ No newline at end of file
+    assert_eq!(part1(&lists), Ok(11));
+    assert_eq!(part2(&lists), Ok(31));
+  }
+
+  #[test]
+  fn distances_wider_than_i32() {
+    let lists = parse_location_lists("-2147483648   2147483647\n-2147483648   2147483647").unwrap();
+
+    assert_eq!(part1(&lists), Ok(8_589_934_590));
+  }
+
+  #[test]
+  fn similarity_scores_wider_than_i32() {
+    let lists = parse_location_lists("2147483647   2147483647\n2147483647   2147483647").unwrap();
+
+    assert_eq!(part2(&lists), Ok(8_589_934_588));
+  }
+}
@@ -33,13 +33,30 @@ So, in this example, 2 reports are safe.
 Analyze the unusual data from the engineers. How many reports are safe?
 */
 
-#[aoc(day2, part1, Chars)]
-pub fn part1(input: &str) -> i32 {
-  let reports = input.lines().map(|line| parse_report(line));
+use std::fmt;
+use std::str::FromStr;
+
+use crate::parse_error::{parse_number, ParseError, ParseErrorKind};
+use crate::table::format_table;
 
-  // reports.for_each(|report| println!("{:?}", report));
+const DAY: u8 = 2;
+
+#[aoc_generator(day2)]
+pub fn parse_reports(input: &str) -> Result<Vec<Report>, ParseError> {
+  input
+    .lines()
+    .enumerate()
+    .map(|(line_index, line)| parse_report(line_index + 1, line))
+    .collect()
+}
+
+#[aoc(day2, part1, Chars)]
+pub fn part1(reports: &[Report]) -> u64 {
+  reports.iter().filter(|report| report.is_safe()).count() as u64
+}
 
-  reports.filter(|report| report.is_safe()).count() as i32
+pub fn count_safe_reports(reports: &[Report], policy: &SafetyPolicy) -> u64 {
+  reports.iter().filter(|report| report.is_safe_with(policy)).count() as u64
 }
 
 /*
@@ -63,22 +80,44 @@ Update your analysis by handling situations where the Problem Dampener can remov
 */
 
 #[aoc(day2, part2, Chars)]
-pub fn part2(input: &str) -> i32 {
-  let reports = input.lines().map(|line| parse_report(line));
+pub fn part2(reports: &[Report]) -> u64 {
+  reports.iter().filter(|report| report.is_safe_part_2()).count() as u64
+}
+
+#[aoc(day2, part2, BruteForce)]
+pub fn part2_brute_force(reports: &[Report]) -> u64 {
+  reports.iter().filter(|report| report.is_safe_part_2_brute_force()).count() as u64
+}
 
-  reports.filter(|report| report.is_safe_part_2()).count() as i32
-  // for report in reports.filter(|report| !report.is_safe() && report.is_safe_part_2()).enumerate() {
-  //   println!("{:?}", report);
-  // }
-  // 12
+// How many reports are safe for each dampener tolerance from 0 up to MAX_TOLERANCE
+#[aoc(day2, part2, ToleranceCurve)]
+pub fn part2_tolerance_curve(reports: &[Report]) -> String {
+  tolerance_curve(reports, MAX_TOLERANCE, &SafetyPolicy::default())
+    .iter()
+    .enumerate()
+    .map(|(tolerance, count)| format!("k={}: {}", tolerance, count))
+    .collect::<Vec<String>>()
+    .join(", ")
 }
 
-fn parse_report(input: &str) -> Report {
+const MAX_TOLERANCE: usize = 3;
+
+// Index k is the number of reports that are safe under the policy after removing at most k levels
+pub fn tolerance_curve(reports: &[Report], max_tolerance: usize, policy: &SafetyPolicy) -> Vec<u64> {
+  (0..=max_tolerance)
+    .map(|tolerance| {
+      let dampener = ProblemDampener::new(tolerance, *policy);
+      reports.iter().filter(|report| dampener.can_make_safe(report)).count() as u64
+    })
+    .collect()
+}
+
+fn parse_report(line_number: usize, input: &str) -> Result<Report, ParseError> {
   let mut levels = Vec::new();
   for level in input.split_whitespace() {
-    levels.push(level.parse::<i32>().unwrap());
+    levels.push(parse_number(DAY, line_number, input, level)?);
   }
-  Report { levels }
+  Ok(Report { levels })
 }
 
 trait IsSafe {
@@ -89,46 +128,360 @@ trait IsSafePart2<T: IsSafe = Self> {
   fn is_safe_part_2(&self) -> bool;
 }
 
-#[derive(Debug)]
-struct Report {
-  levels: Vec<i32>,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+  pub levels: Vec<i32>,
 }
 
 impl IsSafe for Report {
   fn is_safe(&self) -> bool {
-    let mut increasing = false;
-    let mut decreasing = false;
-    let mut previous_value = None;
-    for level in self.levels.iter() {
-      if previous_value.is_none() {
-        previous_value = Some(level);
+    self.is_safe_with(&SafetyPolicy::default())
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllowedDirection {
+  Increasing,
+  Decreasing,
+  // All increasing or all decreasing, whichever the first step is
+  Either,
+  // Like Either, but equal levels may repeat as often as they like whatever max_plateau says
+  NonStrict,
+  // Levels may rise and fall within one report, so only the step sizes and plateaus are checked
+  Any,
+}
+
+impl FromStr for AllowedDirection {
+  type Err = ();
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value {
+      "increasing" => Ok(AllowedDirection::Increasing),
+      "decreasing" => Ok(AllowedDirection::Decreasing),
+      "either" => Ok(AllowedDirection::Either),
+      "non-strict" => Ok(AllowedDirection::NonStrict),
+      "any" => Ok(AllowedDirection::Any),
+      _ => Err(()),
+    }
+  }
+}
+
+// Steps between equal levels are plateaus rather than steps: they never change direction, ignore
+// min_step/max_step and are only limited by max_plateau, the most equal steps allowed in a row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SafetyPolicy {
+  pub min_step: u32,
+  pub max_step: u32,
+  pub direction: AllowedDirection,
+  pub max_plateau: usize,
+}
+
+impl Default for SafetyPolicy {
+  // The puzzle's rules
+  fn default() -> Self {
+    SafetyPolicy {
+      min_step: 1,
+      max_step: 3,
+      direction: AllowedDirection::Either,
+      max_plateau: 0,
+    }
+  }
+}
+
+impl SafetyPolicy {
+  // A flat `key = value` file, e.g.
+  //   min_step = 1
+  //   max_step = 5
+  //   direction = "increasing"
+  //   max_plateau = 2
+  // Keys that are left out keep the puzzle's value. Errors are reported against the policy file's lines.
+  pub fn from_toml(input: &str) -> Result<SafetyPolicy, ParseError> {
+    SafetyPolicy::default().merge_toml(input)
+  }
+
+  // Like from_toml, but keys that are left out keep this policy's value.
+  // A min_step above max_step is reported at whichever of the two was set last, once every key is read.
+  pub fn merge_toml(self, input: &str) -> Result<SafetyPolicy, ParseError> {
+    let mut policy = self;
+    let mut last_step = None;
+
+    for (line_index, line) in input.lines().enumerate() {
+      let line_number = line_index + 1;
+      let content = line.split('#').next().unwrap_or_default().trim();
+      if content.is_empty() || (content.starts_with('[') && content.ends_with(']')) {
         continue;
       }
 
-      if level > previous_value.unwrap() {
-        increasing = true;
+      let Some((key, value)) = content.split_once('=') else {
+        return Err(ParseError::at_token(DAY, line_number, line, content, ParseErrorKind::MissingSeparator("=")));
+      };
+      let (key, value) = (key.trim(), value.trim().trim_matches('"'));
+      policy.set(line_number, line, key, value)?;
+      if key == "min_step" || key == "max_step" {
+        last_step = Some((line_number, line, value));
       }
-      if level < previous_value.unwrap() {
-        decreasing = true;
+    }
+
+    if let Some((line_number, line, value)) = last_step {
+      if policy.min_step > policy.max_step {
+        let kind = ParseErrorKind::StepRange { min_step: policy.min_step, max_step: policy.max_step };
+        return Err(ParseError::at_token(DAY, line_number, line, value, kind));
       }
-      if (increasing && decreasing) || !is_level_diff_in_range(level, previous_value.unwrap()) {
-        return false;
+    }
+
+    Ok(policy)
+  }
+
+  // `key` and `value` must be slices of `line` so errors point at the right column
+  fn set(&mut self, line_number: usize, line: &str, key: &str, value: &str) -> Result<(), ParseError> {
+    let invalid_value = || ParseError::at_token(DAY, line_number, line, value, ParseErrorKind::InvalidValue);
+    match key {
+      "min_step" => self.min_step = u32::try_from(parse_number(DAY, line_number, line, value)?).map_err(|_| invalid_value())?,
+      "max_step" => self.max_step = u32::try_from(parse_number(DAY, line_number, line, value)?).map_err(|_| invalid_value())?,
+      "max_plateau" => self.max_plateau = usize::try_from(parse_number(DAY, line_number, line, value)?).map_err(|_| invalid_value())?,
+      "direction" => self.direction = value.parse().map_err(|_| invalid_value())?,
+      _ => return Err(ParseError::at_token(DAY, line_number, line, key, ParseErrorKind::UnknownKey)),
+    }
+
+    Ok(())
+  }
+}
+
+impl Report {
+  fn is_safe_with(&self, policy: &SafetyPolicy) -> bool {
+    self.first_problem(policy).is_none()
+  }
+
+  // The index of the first level that breaks the policy, and why
+  fn first_problem(&self, policy: &SafetyPolicy) -> Option<(usize, UnsafeReason)> {
+    let mut first_step_increasing = None;
+    let mut plateau = 0;
+
+    for (i, pair) in self.levels.windows(2).enumerate() {
+      if pair[1] == pair[0] {
+        plateau += 1;
+        if plateau > policy.plateau_limit() {
+          return Some((i + 1, UnsafeReason::Plateau));
+        }
+        continue;
+      }
+      plateau = 0;
+
+      let step = pair[1].abs_diff(pair[0]);
+      if step < policy.min_step {
+        return Some((i + 1, UnsafeReason::StepTooSmall));
+      }
+      if step > policy.max_step {
+        return Some((i + 1, UnsafeReason::StepTooBig));
       }
 
-      previous_value = Some(level);
+      let increasing = pair[1] > pair[0];
+      let direction_allowed = match policy.direction {
+        AllowedDirection::Increasing => increasing,
+        AllowedDirection::Decreasing => !increasing,
+        AllowedDirection::Either | AllowedDirection::NonStrict => {
+          *first_step_increasing.get_or_insert(increasing) == increasing
+        }
+        AllowedDirection::Any => true,
+      };
+      if !direction_allowed {
+        return Some((i + 1, UnsafeReason::DirectionFlip));
+      }
     }
 
-    true
+    None
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnsafeReason {
+  DirectionFlip,
+  StepTooBig,
+  StepTooSmall,
+  Plateau,
+}
+
+impl fmt::Display for UnsafeReason {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      UnsafeReason::DirectionFlip => "direction_flip",
+      UnsafeReason::StepTooBig => "step_too_big",
+      UnsafeReason::StepTooSmall => "step_too_small",
+      UnsafeReason::Plateau => "plateau",
+    })
   }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+  Safe,
+  SafeWithDampener,
+  Unsafe,
+}
+
+impl fmt::Display for Verdict {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      Verdict::Safe => "safe",
+      Verdict::SafeWithDampener => "safe_with_dampener",
+      Verdict::Unsafe => "unsafe",
+    })
+  }
+}
+
+// Indexes are 0-based positions in `levels`; `line` is the 1-based line of the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportAnalysis {
+  pub line: usize,
+  pub levels: Vec<i32>,
+  pub verdict: Verdict,
+  pub first_problem: Option<(usize, UnsafeReason)>,
+  pub removed_level: Option<usize>,
+}
+
+// Every report checked against the policy and the single-level Problem Dampener
+pub fn analyze(reports: &[Report], policy: &SafetyPolicy) -> Vec<ReportAnalysis> {
+  reports
+    .iter()
+    .enumerate()
+    .map(|(index, report)| {
+      let first_problem = report.first_problem(policy);
+      let (verdict, removed_level) = match report.removable_level(policy) {
+        Dampening::AlreadySafe => (Verdict::Safe, None),
+        Dampening::RemoveLevel(removed) => (Verdict::SafeWithDampener, Some(removed)),
+        Dampening::Unsafe => (Verdict::Unsafe, None),
+      };
+      ReportAnalysis {
+        line: index + 1,
+        levels: report.levels.clone(),
+        verdict,
+        first_problem,
+        removed_level,
+      }
+    })
+    .collect()
+}
+
+pub fn analysis_table(analysis: &[ReportAnalysis]) -> String {
+  let optional = |value: Option<String>| value.unwrap_or_else(|| String::from("-"));
+  let rows = analysis
+    .iter()
+    .map(|report| {
+      vec![
+        report.line.to_string(),
+        report.levels.iter().map(|level| level.to_string()).collect::<Vec<String>>().join(" "),
+        report.verdict.to_string(),
+        optional(report.first_problem.map(|(index, reason)| format!("{} at {}", reason, index))),
+        optional(report.removed_level.map(|index| index.to_string())),
+      ]
+    })
+    .collect::<Vec<Vec<String>>>();
+
+  format_table(&["line", "levels", "verdict", "first problem", "removed"], &rows)
+}
+
+pub fn analysis_json(analysis: &[ReportAnalysis]) -> String {
+  let optional = |value: Option<String>| value.unwrap_or_else(|| String::from("null"));
+  let reports = analysis
+    .iter()
+    .map(|report| {
+      format!(
+        "{{\"line\":{},\"levels\":[{}],\"verdict\":\"{}\",\"first_problem\":{},\"removed_level\":{}}}",
+        report.line,
+        report.levels.iter().map(|level| level.to_string()).collect::<Vec<String>>().join(","),
+        report.verdict,
+        optional(report.first_problem.map(|(index, reason)| format!("{{\"index\":{},\"reason\":\"{}\"}}", index, reason))),
+        optional(report.removed_level.map(|index| index.to_string())),
+      )
+    })
+    .collect::<Vec<String>>();
+
+  format!("[{}]\n", reports.join(",\n"))
+}
+
 impl IsSafePart2 for Report {
   fn is_safe_part_2(&self) -> bool {
+    self.removable_level(&SafetyPolicy::default()) != Dampening::Unsafe
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dampening {
+  AlreadySafe,
+  RemoveLevel(usize),
+  Unsafe,
+}
+
+impl SafetyPolicy {
+  // The directions a whole report may go in: Some(true) for increasing, Some(false) for decreasing and None
+  // when rises and falls can be mixed. A report is safe if it is safe in any one of them.
+  fn directions(&self) -> &'static [Option<bool>] {
+    match self.direction {
+      AllowedDirection::Increasing => &[Some(true)],
+      AllowedDirection::Decreasing => &[Some(false)],
+      AllowedDirection::Either | AllowedDirection::NonStrict => &[Some(true), Some(false)],
+      AllowedDirection::Any => &[None],
+    }
+  }
+
+  // The most equal steps allowed in a row
+  fn plateau_limit(&self) -> usize {
+    match self.direction {
+      AllowedDirection::NonStrict => usize::MAX,
+      _ => self.max_plateau,
+    }
+  }
+
+  // Whether `level` may follow `previous` in `direction` when the `plateau` steps before it were all equal
+  fn allows_step(&self, previous: i32, level: i32, direction: Option<bool>, plateau: usize) -> bool {
+    if level == previous {
+      return plateau < self.plateau_limit();
+    }
+
+    let step = level.abs_diff(previous);
+    (self.min_step..=self.max_step).contains(&step) && direction.is_none_or(|increasing| (level > previous) == increasing)
+  }
+}
+
+impl Report {
+  // One scan per direction finds the first step that breaks the policy. Removing any level outside that step
+  // leaves its two levels next to each other, and a plateau's levels are all equal, so only those two levels are
+  // worth retrying. O(n) and no allocations.
+  fn removable_level(&self, policy: &SafetyPolicy) -> Dampening {
+    let directions = policy.directions();
+    if directions.iter().any(|direction| first_bad_step(&self.levels, policy, *direction, None).is_none()) {
+      return Dampening::AlreadySafe;
+    }
+
+    for direction in directions {
+      let Some(position) = first_bad_step(&self.levels, policy, *direction, None) else {
+        continue;
+      };
+      for removed in [position - 1, position] {
+        if first_bad_step(&self.levels, policy, *direction, Some(removed)).is_none() {
+          return Dampening::RemoveLevel(removed);
+        }
+      }
+    }
+
+    Dampening::Unsafe
+  }
+
+  // The original clone-and-retry version, kept to check removable_level against. O(n²) with an allocation per retry.
+  fn is_safe_part_2_brute_force(&self) -> bool {
+    self.is_safe_removing_one_brute_force(&SafetyPolicy::default())
+  }
+
+  fn is_safe_removing_one_brute_force(&self, policy: &SafetyPolicy) -> bool {
+    if self.is_safe_with(policy) {
+      return true;
+    }
+
     for (i, _) in self.levels.iter().enumerate() {
       let mut levels = self.levels.clone();
       levels.remove(i);
 
-      if (Report { levels }.is_safe()) {
+      if (Report { levels }.is_safe_with(policy)) {
         return true;
       }
     }
@@ -137,14 +490,335 @@ impl IsSafePart2 for Report {
   }
 }
 
-fn is_level_diff_in_range(level: &i32, previous_value: &i32) -> bool {
-  let level_diff = (level - previous_value).abs();
+// The position of the second level of the first step that breaks the policy in `direction`, ignoring the level
+// at `removed`
+fn first_bad_step(levels: &[i32], policy: &SafetyPolicy, direction: Option<bool>, removed: Option<usize>) -> Option<usize> {
+  let mut previous_value = None;
+  let mut plateau = 0;
+  for (i, level) in levels.iter().enumerate() {
+    if Some(i) == removed {
+      continue;
+    }
+    if let Some(previous_value) = previous_value {
+      if !policy.allows_step(previous_value, *level, direction, plateau) {
+        return Some(i);
+      }
+      plateau = if *level == previous_value { plateau + 1 } else { 0 };
+    }
+    previous_value = Some(*level);
+  }
+
+  None
+}
+
+struct ProblemDampener {
+  tolerance: usize,
+  policy: SafetyPolicy,
+}
+
+impl ProblemDampener {
+  fn new(tolerance: usize, policy: SafetyPolicy) -> ProblemDampener {
+    ProblemDampener { tolerance, policy }
+  }
+
+  fn can_make_safe(&self, report: &Report) -> bool {
+    report.min_removals(&self.policy) <= self.tolerance
+  }
+}
+
+impl Report {
+  // The fewest levels that have to be removed for the rest to be safe.
+  // longest[i][p] is the longest safe run of kept levels that ends with level i after p equal steps in a row,
+  // or 0 if there is none. It is built from every earlier level the step to i is allowed from, so this is
+  // O(n²·p) per direction instead of trying every combination of removals.
+  fn min_removals(&self, policy: &SafetyPolicy) -> usize {
+    let levels = &self.levels;
+    let plateaus = policy.plateau_limit().min(levels.len());
+    let mut longest_safe = 0;
+
+    for direction in policy.directions() {
+      let mut longest: Vec<Vec<usize>> = vec![vec![0; plateaus + 1]; levels.len()];
+      for i in 0..levels.len() {
+        longest[i][0] = 1;
+        for j in 0..i {
+          for plateau in 0..=plateaus {
+            if longest[j][plateau] == 0 || !policy.allows_step(levels[j], levels[i], *direction, plateau) {
+              continue;
+            }
+            let next_plateau = if levels[i] == levels[j] { plateau + 1 } else { 0 };
+            longest[i][next_plateau] = longest[i][next_plateau].max(longest[j][plateau] + 1);
+          }
+        }
+      }
+      longest_safe = longest_safe.max(longest.iter().flatten().copied().max().unwrap_or(0));
+    }
+
+    levels.len() - longest_safe
+  }
+}
+
+#[cfg(test)]
+mod parse_report_tests {
+  use super::*;
+  use crate::parse_error::ParseErrorKind;
+
+  #[test]
+  fn parses_levels() {
+    assert_eq!(parse_report(1, "7 6 4 2 1").unwrap().levels, vec![7, 6, 4, 2, 1]);
+  }
+
+  #[test]
+  fn invalid_level_reports_line_and_column() {
+    let error = parse_reports("7 6 4 2 1\n1 2 7a 8 9").unwrap_err();
+
+    assert_eq!(error, ParseError::new(DAY, 2, 5, "7a", ParseErrorKind::InvalidNumber));
+  }
+}
+
+#[cfg(test)]
+mod safety_policy_tests {
+  use super::*;
+
+  #[test]
+  fn default_policy_is_the_puzzle() {
+    let input = "7 6 4 2 1\n1 2 7 8 9\n9 7 6 2 1\n1 3 2 4 5\n8 6 4 4 1\n1 3 6 7 9";
+
+    assert_eq!(count_safe_reports(&parse_reports(input).unwrap(), &SafetyPolicy::default()), 2);
+  }
+
+  #[test]
+  fn policy_limits_steps_direction_and_plateaus() {
+    let policy = SafetyPolicy {
+      min_step: 2,
+      max_step: 5,
+      direction: AllowedDirection::Increasing,
+      max_plateau: 1,
+    };
+
+    assert!(Report { levels: vec![1, 3, 3, 8] }.is_safe_with(&policy));
+    assert!(!Report { levels: vec![1, 3, 3, 3, 8] }.is_safe_with(&policy));
+    assert!(!Report { levels: vec![1, 2, 4] }.is_safe_with(&policy));
+    assert!(!Report { levels: vec![8, 6, 4] }.is_safe_with(&policy));
+  }
+
+  #[test]
+  fn any_direction_allows_rises_and_falls() {
+    let policy = SafetyPolicy { direction: AllowedDirection::Any, ..SafetyPolicy::default() };
+
+    assert!(Report { levels: vec![1, 3, 2, 4, 5] }.is_safe_with(&policy));
+  }
+
+  #[test]
+  fn non_strict_direction_allows_equal_levels_but_not_turns() {
+    let policy = SafetyPolicy::from_toml("direction = \"non-strict\"").unwrap();
+
+    assert_eq!(policy.direction, AllowedDirection::NonStrict);
+    assert!(Report { levels: vec![1, 1, 1, 3, 3, 4] }.is_safe_with(&policy));
+    assert!(Report { levels: vec![9, 9, 7, 7, 7, 6] }.is_safe_with(&policy));
+    assert!(!Report { levels: vec![1, 3, 3, 2] }.is_safe_with(&policy));
+    assert!(!Report { levels: vec![1, 1, 5] }.is_safe_with(&policy));
+  }
+
+  #[test]
+  fn loads_from_toml() {
+    let policy = SafetyPolicy::from_toml("[policy]\n# wider reactor\nmax_step = 5\ndirection = \"decreasing\"\n").unwrap();
+
+    assert_eq!(
+      policy,
+      SafetyPolicy { max_step: 5, direction: AllowedDirection::Decreasing, ..SafetyPolicy::default() }
+    );
+  }
+
+  #[test]
+  fn toml_errors_point_at_the_line() {
+    assert_eq!(
+      SafetyPolicy::from_toml("max_step = 5\nmax_stride = 3").unwrap_err(),
+      ParseError::new(DAY, 2, 1, "max_stride", ParseErrorKind::UnknownKey)
+    );
+    assert_eq!(
+      SafetyPolicy::from_toml("direction = \"sideways\"").unwrap_err(),
+      ParseError::new(DAY, 1, 14, "sideways", ParseErrorKind::InvalidValue)
+    );
+  }
+
+  #[test]
+  fn min_step_above_max_step_is_an_error() {
+    assert_eq!(
+      SafetyPolicy::from_toml("max_step = 2\nmin_step = 5").unwrap_err(),
+      ParseError::new(DAY, 2, 12, "5", ParseErrorKind::StepRange { min_step: 5, max_step: 2 })
+    );
+    assert_eq!(
+      SafetyPolicy::from_toml("min_step = 4\nmax_step = 6").unwrap().merge_toml("max_step = 2").unwrap_err(),
+      ParseError::new(DAY, 1, 12, "2", ParseErrorKind::StepRange { min_step: 4, max_step: 2 })
+    );
+    assert!(SafetyPolicy::from_toml("min_step = 4\nmax_step = 2").unwrap_err().to_string().ends_with("min_step 4 is above max_step 2"));
+    assert_eq!(SafetyPolicy::from_toml("min_step = 5\nmax_step = 5").unwrap().min_step, 5);
+  }
+}
+
+#[cfg(test)]
+mod analyze_tests {
+  use super::*;
+
+  const EXAMPLE: &str = "7 6 4 2 1\n1 2 7 8 9\n9 7 6 2 1\n1 3 2 4 5\n8 6 4 4 1\n1 3 6 7 9";
+
+  #[test]
+  fn explains_each_report_of_the_example() {
+    let analysis = analyze(&parse_reports(EXAMPLE).unwrap(), &SafetyPolicy::default());
+
+    let summary = analysis
+      .iter()
+      .map(|report| (report.verdict, report.first_problem, report.removed_level))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      summary,
+      vec![
+        (Verdict::Safe, None, None),
+        (Verdict::Unsafe, Some((2, UnsafeReason::StepTooBig)), None),
+        (Verdict::Unsafe, Some((3, UnsafeReason::StepTooBig)), None),
+        (Verdict::SafeWithDampener, Some((2, UnsafeReason::DirectionFlip)), Some(1)),
+        (Verdict::SafeWithDampener, Some((3, UnsafeReason::Plateau)), Some(2)),
+        (Verdict::Safe, None, None),
+      ]
+    );
+  }
+
+  #[test]
+  fn dumps_as_table_and_json() {
+    let analysis = analyze(&parse_reports("1 3 2 4 5\n7 6 4 2 1").unwrap(), &SafetyPolicy::default());
+
+    assert_eq!(
+      analysis_table(&analysis),
+      "line | levels    | verdict            | first problem       | removed
+1    | 1 3 2 4 5 | safe_with_dampener | direction_flip at 2 | 1
+2    | 7 6 4 2 1 | safe               | -                   | -
+"
+    );
+    assert_eq!(
+      analysis_json(&analysis),
+      "[{\"line\":1,\"levels\":[1,3,2,4,5],\"verdict\":\"safe_with_dampener\",\"first_problem\":{\"index\":2,\"reason\":\"direction_flip\"},\"removed_level\":1},
+{\"line\":2,\"levels\":[7,6,4,2,1],\"verdict\":\"safe\",\"first_problem\":null,\"removed_level\":null}]
+"
+    );
+  }
+}
+
+#[cfg(test)]
+mod problem_dampener_tests {
+  use super::*;
+
+  #[test]
+  fn tolerance_of_zero_matches_is_safe() {
+    let dampener = ProblemDampener::new(0, SafetyPolicy::default());
+
+    assert!(dampener.can_make_safe(&Report { levels: vec![7, 6, 4, 2, 1] }));
+    assert!(!dampener.can_make_safe(&Report { levels: vec![1, 3, 2, 4, 5] }));
+  }
+
+  #[test]
+  fn tolerance_of_one_matches_is_safe_part_2() {
+    let dampener = ProblemDampener::new(1, SafetyPolicy::default());
+
+    for levels in [vec![1, 2, 7, 8, 9], vec![9, 7, 6, 2, 1], vec![1, 3, 2, 4, 5], vec![8, 6, 4, 4, 1], vec![18, 5, 4, 3, 22, 2]] {
+      let report = Report { levels };
+      assert_eq!(dampener.can_make_safe(&report), report.is_safe_part_2(), "{:?}", report);
+    }
+  }
+
+  #[test]
+  fn higher_tolerance_removes_more_levels() {
+    let report = Report { levels: vec![18, 5, 4, 3, 22, 2] };
+
+    assert_eq!(report.min_removals(&SafetyPolicy::default()), 2);
+    assert!(ProblemDampener::new(2, SafetyPolicy::default()).can_make_safe(&report));
+  }
+
+  #[test]
+  fn tolerance_curve_of_the_example() {
+    let input = "7 6 4 2 1\n1 2 7 8 9\n9 7 6 2 1\n1 3 2 4 5\n8 6 4 4 1\n1 3 6 7 9";
+
+    assert_eq!(tolerance_curve(&parse_reports(input).unwrap(), 3, &SafetyPolicy::default()), vec![2, 4, 6, 6]);
+  }
+}
+
+#[cfg(test)]
+mod removable_level_tests {
+  use super::*;
+  use proptest::prelude::*;
+
+  #[test]
+  fn identifies_the_level_to_remove() {
+    assert_eq!(Report { levels: vec![7, 6, 4, 2, 1] }.removable_level(&SafetyPolicy::default()), Dampening::AlreadySafe);
+    assert_eq!(Report { levels: vec![1, 3, 2, 4, 5] }.removable_level(&SafetyPolicy::default()), Dampening::RemoveLevel(1));
+    assert_eq!(Report { levels: vec![8, 6, 4, 4, 1] }.removable_level(&SafetyPolicy::default()), Dampening::RemoveLevel(2));
+    assert_eq!(Report { levels: vec![18, 2, 3, 4, 5] }.removable_level(&SafetyPolicy::default()), Dampening::RemoveLevel(0));
+    assert_eq!(Report { levels: vec![1, 2, 7, 8, 9] }.removable_level(&SafetyPolicy::default()), Dampening::Unsafe);
+  }
+
+  proptest! {
+    #[test]
+    fn agrees_with_brute_force(levels in prop::collection::vec(0..12i32, 1..9)) {
+      let report = Report { levels };
+
+      prop_assert_eq!(report.is_safe_part_2(), report.is_safe_part_2_brute_force());
+    }
+
+    #[test]
+    fn removing_the_reported_level_makes_the_report_safe(levels in prop::collection::vec(0..12i32, 1..9)) {
+      let report = Report { levels };
+
+      match report.removable_level(&SafetyPolicy::default()) {
+        Dampening::AlreadySafe => prop_assert!(report.is_safe()),
+        Dampening::RemoveLevel(removed) => {
+          let mut levels = report.levels.clone();
+          levels.remove(removed);
+          let dampened_report = Report { levels };
+          prop_assert!(!report.is_safe());
+          prop_assert!(dampened_report.is_safe());
+        }
+        Dampening::Unsafe => prop_assert!(!report.is_safe_part_2_brute_force()),
+      }
+    }
 
-  if level_diff >= 1 && level_diff <= 3 {
-    return true;
+    #[test]
+    fn agrees_with_brute_force_under_any_policy(levels in prop::collection::vec(0..12i32, 1..9), policy in policies()) {
+      let report = Report { levels };
+
+      prop_assert_eq!(
+        report.removable_level(&policy) != Dampening::Unsafe,
+        report.is_safe_removing_one_brute_force(&policy)
+      );
+      prop_assert_eq!(report.min_removals(&policy) == 0, report.is_safe_with(&policy));
+      prop_assert_eq!(report.min_removals(&policy) <= 1, report.is_safe_removing_one_brute_force(&policy));
+    }
   }
 
-  false
+  fn policies() -> impl Strategy<Value = SafetyPolicy> {
+    let directions = prop_oneof![
+      Just(AllowedDirection::Increasing),
+      Just(AllowedDirection::Decreasing),
+      Just(AllowedDirection::Either),
+      Just(AllowedDirection::NonStrict),
+      Just(AllowedDirection::Any),
+    ];
+    (0..3u32, 0..5u32, directions, 0..3usize).prop_map(|(min_step, extra, direction, max_plateau)| SafetyPolicy {
+      min_step,
+      max_step: min_step + extra,
+      direction,
+      max_plateau,
+    })
+  }
+
+  #[test]
+  fn dampens_with_a_custom_policy() {
+    let policy = SafetyPolicy { max_step: 5, max_plateau: 1, ..SafetyPolicy::default() };
+    let report = Report { levels: vec![1, 6, 6, 6, 9] };
+
+    assert_eq!(report.removable_level(&policy), Dampening::RemoveLevel(2));
+    assert_eq!(report.removable_level(&SafetyPolicy::default()), Dampening::Unsafe);
+    assert_eq!(report.min_removals(&policy), 1);
+    assert_eq!(report.min_removals(&SafetyPolicy::default()), 3);
+  }
 }
 
 #[cfg(test)]
@@ -160,7 +834,7 @@ mod tests_part_2 {
   #[test]
   fn when_the_report_is_not_safe_then_the_report_is_not_safe_part_2() {
     let bad_report = Report { levels: vec![1, 2, 7, 14, 19] };
-    assert!(bad_report.is_safe_part_2() == false);
+    assert!(!bad_report.is_safe_part_2());
   }
 
   #[test]
@@ -190,7 +864,7 @@ mod tests_part_2 {
   #[test]
   fn when_the_report_requires_multiple_removals_to_be_safe_then_the_report_is_not_safe_part_2() {
     let bad_report = Report { levels: vec![18, 5, 4, 3, 22, 2] };
-    assert!(bad_report.is_safe_part_2() == false);
+    assert!(!bad_report.is_safe_part_2());
   }
 }
 
@@ -201,19 +875,27 @@ mod tests_part_1 {
   #[test]
   fn when_there_is_a_change_in_levels_greater_than_three_then_the_report_is_not_safe() {
     let bad_report = Report { levels: vec![1, 2, 7, 8, 9] };
-    assert!(bad_report.is_safe() == false);
+    assert!(!bad_report.is_safe());
   }
 
   #[test]
   fn when_there_is_a_change_in_levels_greater_less_than_one_then_the_report_is_not_safe() {
     let bad_report = Report { levels: vec![1, 2, 2, 3, 4] };
-    assert!(bad_report.is_safe() == false);
+    assert!(!bad_report.is_safe());
   }
 
   #[test]
   fn when_there_is_a_mix_of_ascending_and_descending_changes_then_the_report_is_not_safe() {
     let bad_report = Report { levels: vec![1, 2, 1, 3, 4] };
-    assert!(bad_report.is_safe() == false);
+    assert!(!bad_report.is_safe());
+  }
+
+  #[test]
+  fn steps_wider_than_i32_are_not_safe() {
+    let extreme_report = Report { levels: vec![i32::MIN, i32::MAX, i32::MIN] };
+    assert!(!extreme_report.is_safe());
+    assert!(!extreme_report.is_safe_part_2());
+    assert_eq!(extreme_report.first_problem(&SafetyPolicy::default()), Some((1, UnsafeReason::StepTooBig)));
   }
 
   #[test]
@@ -227,4 +909,19 @@ mod tests_part_1 {
     let bad_report = Report { levels: vec![4, 3, 2, 1] };
     assert!(bad_report.is_safe());
   }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod part_tests {
+  use super::*;
+
+  #[test]
+  fn provided_example() {
+    let reports = parse_reports("7 6 4 2 1\n1 2 7 8 9\n9 7 6 2 1\n1 3 2 4 5\n8 6 4 4 1\n1 3 6 7 9").unwrap();
+
+    assert_eq!(part1(&reports), 2);
+    assert_eq!(part2(&reports), 4);
+    assert_eq!(part2_brute_force(&reports), 4);
+    assert_eq!(part2_tolerance_curve(&reports), "k=0: 2, k=1: 4, k=2: 6, k=3: 6");
+  }
+}
@@ -37,221 +37,54 @@ S.S.S.S.SS
 .X.X.XMASX
 Take a look at the little Elf's word search. How many times does XMAS appear?
 */
-use regex::Regex;
+use crate::grid::Grid;
+use crate::parse_error::ParseError;
+use crate::pattern::PatternTemplate;
+use crate::render::{render_placements, render_word_matches};
+use crate::word_search::WordSearch;
 
-#[aoc(day4, part1, Chars)]
-pub fn part1(input: &str) -> i32 {
-  // vertical line search (forward and backwards)
-  // diagonal line search (forward and backwards)
-  let mut total = 0;
-  let xmas_finder = Regex::new(r"XMAS").unwrap();
-  let samx_finder = Regex::new(r"SAMX").unwrap();
-
-  // horizontal line search (forward and backwards)
-  total += search_horizontal(input.lines().collect(), &xmas_finder, &samx_finder);
-  total += search_vertical(input.lines().collect(), &xmas_finder, &samx_finder);
-  total += search_diagonal(input.lines().collect(), &xmas_finder, &samx_finder);
-
-  total
-}
-
-#[aoc(day4, part2, Chars)]
-pub fn part2(str_input: &str) -> i32 {
-  let mut total = 0;
-  // Convert input to Vec<String>
-  let input = str_input.lines().map(|line| line.to_string()).collect::<Vec<String>>();
-
-
-  let indexable_input = IndexableInput { input };
-
-  for y in 0..str_input.lines().count() {
-    let line = str_input.lines().nth(y).unwrap();
-    if y + 2 > str_input.lines().count() - 1 {
-      break;
-    }
-    for x in 0..line.len() {
-      if x + 2 > line.len() - 1 {
-        break;
-      }
-
-      let char = indexable_input.get(x, y);
-      /*
-      M-M
-      -A-
-      S-S
-      */
-      if char == 'M'
-        && indexable_input.get(x + 2, y) == 'M'
-        && indexable_input.get(x + 1, y + 1) == 'A'
-        && indexable_input.get(x, y + 2) == 'S'
-        && indexable_input.get(x + 2, y + 2) == 'S' {
-          total += 1;
-          continue;
-      }
-
-      /*
-      S-S
-      -A-
-      M-M
-      */
-      if char == 'S'
-        && indexable_input.get(x + 2, y) == 'S'
-        && indexable_input.get(x + 1, y + 1) == 'A'
-        && indexable_input.get(x, y + 2) == 'M'
-        && indexable_input.get(x + 2, y + 2) == 'M' {
-          total += 1;
-          continue;
-      }
-
-      /*
-      M-S
-      -A-
-      M-S
-      */
-      if char == 'M'
-        && indexable_input.get(x + 2, y) == 'S'
-        && indexable_input.get(x + 1, y + 1) == 'A'
-        && indexable_input.get(x, y + 2) == 'M'
-        && indexable_input.get(x + 2, y + 2) == 'S' {
-          total += 1;
-          continue;
-      }
-
-      /*
-      S-M
-      -A-
-      S-M
-      */
-      if char == 'S'
-        && indexable_input.get(x + 2, y) == 'M'
-        && indexable_input.get(x + 1, y + 1) == 'A'
-        && indexable_input.get(x, y + 2) == 'S'
-        && indexable_input.get(x + 2, y + 2) == 'M' {
-          total += 1;
-          continue;
-      }
-    }
-  }
+const DAY: u8 = 4;
 
-  total
+#[aoc_generator(day4)]
+pub fn parse_grid(input: &str) -> Result<Grid<char>, ParseError> {
+  Grid::try_parse(DAY, input)
 }
 
-struct IndexableInput {
-  input: Vec<String>
+#[aoc(day4, part1, Chars)]
+pub fn part1(grid: &Grid<char>) -> u64 {
+  // horizontal, vertical and diagonal, forward and backwards
+  xmas_search().count(grid) as u64
 }
 
-trait Indexable {
-  fn get(&self, x: usize, y: usize) -> char;
+#[aoc(day4, part2, Chars)]
+pub fn part2(grid: &Grid<char>) -> u64 {
+  x_mas_template().count(grid) as u64
 }
 
-impl Indexable for IndexableInput {
-  fn get(&self, x: usize, y: usize) -> char {
-      self.input[y].chars().nth(x).unwrap()
-  }
+// The part1 grid with every letter that is not part of an XMAS replaced by `.`
+pub fn render_part1(grid: &Grid<char>, colour: bool) -> String {
+  render_word_matches(grid, &xmas_search().find(grid), colour)
 }
 
-fn search_horizontal(input: Vec<&str>, xmas_finder: &Regex, samx_finder: &Regex) -> i32 {
-  let mut total = 0;
+// The part2 grid with every letter that is not part of an X-MAS replaced by `.`
+pub fn render_part2(grid: &Grid<char>, colour: bool) -> String {
+  let template = x_mas_template();
 
-  for line in input {
-    total += xmas_finder.captures_iter(line).count();
-    total += samx_finder.captures_iter(line).count();
-  }
-
-  // I know this is dangerous
-  i32::try_from(total).unwrap()
+  render_placements(grid, &template, &template.find(grid), colour)
 }
 
-fn search_vertical(input: Vec<&str>, xmas_finder: &Regex, samx_finder: &Regex) -> i32 {
-  let mut total = 0;
-
-  for i in 0..input[0].len() {
-    let mut line = String::new();
-    for j in 0..input.len() {
-      line.push(input[j].chars().nth(i).unwrap());
-    }
-    total += xmas_finder.captures_iter(&line).count();
-    total += samx_finder.captures_iter(&line).count();
-  }
-
-  i32::try_from(total).unwrap()
+fn xmas_search() -> WordSearch<'static> {
+  WordSearch::new(&["XMAS"])
 }
 
-fn search_diagonal(input: Vec<&str>, xmas_finder: &Regex, samx_finder: &Regex) -> i32 {
-  let mut total = 0;
-  let dimension_length = input.len();
-
-  // Assumptions that all lines are of equal length
-  // Conveniently the input is a square
-  let mut lines_to_search: Vec<String> = vec![];
-  // Top Left
-  for x in 0..dimension_length {
-    let mut line = String::new();
-    for y in 0..dimension_length {
-      if x < y {
-        break;
-      }
-      line.push(input[x - y].chars().nth(y).unwrap());
-    }
-    if line.len() < 4 {
-      continue;
-    }
-    lines_to_search.push(line);
-  }
-
-  // Top Right
-  for y in (0..dimension_length).rev() {
-    let mut line = String::new();
-    for x in 0..dimension_length {
-      if x + y > dimension_length - 1 {
-        break;
-      }
-      line.push(input[x].chars().nth(y + x).unwrap());
-    }
-    if line.len() < 4 {
-      continue;
-    }
-    lines_to_search.push(line);
-  }
-
-  // Bottom Left
-  for x in (1..dimension_length).rev() {
-    let mut line = String::new();
-    for y in 0..dimension_length {
-      if x + y > dimension_length - 1 {
-        break;
-      }
-      line.push(input[x + y].chars().nth(y).unwrap());
-    }
-    if line.len() < 4 {
-      continue;
-    }
-    lines_to_search.push(line);
-  }
-
-  // Bottom Right
-  for x in (1..dimension_length).rev() {
-    let mut line = String::new();
-    for y in (0..dimension_length).rev() {
-      if y < x {
-        break;
-      }
-      let factor = dimension_length - 1 - y;
-
-      line.push(input[x + factor].chars().nth(y).unwrap());
-    }
-    if line.len() < 4 {
-      continue;
-    }
-    lines_to_search.push(line);
-  }
-
-  for line_to_search in lines_to_search {
-    total += xmas_finder.captures_iter(&line_to_search).count();
-    total += samx_finder.captures_iter(&line_to_search).count();
-  }
-
-  i32::try_from(total).unwrap()
+fn x_mas_template() -> PatternTemplate {
+  /*
+  M-S
+  -A-
+  M-S
+  plus its rotations and reflections
+  */
+  PatternTemplate::parse(DAY, "M.S\n.A.\nM.S").expect("the X-MAS mask is valid")
 }
 
 #[cfg(test)]
@@ -260,20 +93,13 @@ mod day4_part1 {
 
   #[test]
   fn overlapping_matches_should_count_twice() {
-    let xmas_finder = Regex::new(r"XMAS").unwrap();
-    let samx_finder = Regex::new(r"SAMX").unwrap();
-    assert_eq!(
-      xmas_finder.captures_iter("XMASAMX").count() +
-      samx_finder.captures_iter("XMASAMX").count(),
-      2
-    );
+    assert_eq!(WordSearch::new(&["XMAS"]).count(&Grid::parse("XMASAMX")), 2);
   }
 
   #[test]
   fn provided_test_case_should_pass() {
-    assert_eq!(
-      part1(
-        "....XXMAS.
+    let grid = parse_grid(
+      "....XXMAS.
 .SAMXMS...
 ...S..A...
 ..A.A.MS.X
@@ -283,36 +109,56 @@ S.S.S.S.SS
 .A.A.A.A.A
 ..M.M.M.MM
 .X.X.XMASX
-"
-      ),
-      18
-    );
+",
+    )
+    .unwrap();
+
+    assert_eq!(part1(&grid), 18);
+  }
+
+  #[test]
+  fn non_square_grids_search_every_diagonal() {
+    let wide = "X.......S\n.M.....A.\n..A...M..\n...S.X...";
+    let tall = "X...\n.M..\n..A.\n...S\n..M.\n.A..\nS...";
+
+    assert_eq!(part1(&Grid::parse(wide)), 2);
+    assert_eq!(part1(&Grid::parse(tall)), 1);
+  }
+
+  #[test]
+  fn ragged_rows_report_the_line() {
+    let error = parse_grid("XMAS\nXMAS\nXMA\n").unwrap_err();
+
+    assert_eq!(error.line, 3);
   }
 }
 
 #[cfg(test)]
 mod search_horizontal_tests {
   use super::*;
+  use crate::grid::Direction;
 
   #[test]
   fn horizontal_search_finds_all_matches() {
-    let input = vec![
+    let input = [
       "AXMASASAMXX",
       "XSAMXSXMASA",
       "XMASSSSAMXM",
       "XXXMASXMASM",
     ];
-    assert_eq!(search_horizontal(input, &Regex::new(r"XMAS").unwrap(), &Regex::new(r"SAMX").unwrap()), 8);
+    let word_search = WordSearch::new(&["XMAS"]).directions(&[Direction::East, Direction::West]);
+    assert_eq!(word_search.count(&Grid::parse(&input.join("\n"))), 8);
   }
 }
 
 #[cfg(test)]
 mod search_vertical_tests {
   use super::*;
+  use crate::grid::Direction;
 
   #[test]
   fn vertical_search_finds_all_matches() {
-    let input = vec![
+    let input = [
       "MM",
       "XS",
       "MA",
@@ -324,28 +170,33 @@ mod search_vertical_tests {
       "XS",
       "XX",
     ];
-    assert_eq!(search_vertical(input, &Regex::new(r"XMAS").unwrap(), &Regex::new(r"SAMX").unwrap()), 4);
+    let word_search = WordSearch::new(&["XMAS"]).directions(&[Direction::North, Direction::South]);
+    assert_eq!(word_search.count(&Grid::parse(&input.join("\n"))), 4);
   }
 }
 
 #[cfg(test)]
 mod search_diagonal_tests {
   use super::*;
+  use crate::grid::Direction;
+
+  const DIAGONALS: [Direction; 4] = [Direction::NorthEast, Direction::SouthEast, Direction::SouthWest, Direction::NorthWest];
 
   #[test]
   fn diagonal_search_finds_all_matches() {
-    let input = vec![
+    let input = [
       "XXXX",
       "MMMM",
       "AAAA",
       "SSSS",
     ];
-    assert_eq!(search_diagonal(input, &Regex::new(r"XMAS").unwrap(), &Regex::new(r"SAMX").unwrap()), 2);
+    let word_search = WordSearch::new(&["XMAS"]).directions(&DIAGONALS);
+    assert_eq!(word_search.count(&Grid::parse(&input.join("\n"))), 2);
   }
 
   #[test]
   fn diagonal_search_finds_all_reversed_matches() {
-    let input = vec![
+    let input = [
       "---SS---",
       "--A--A-X",
       "-M----M-",
@@ -355,7 +206,8 @@ mod search_diagonal_tests {
       "--M-----",
       "-X-X----",
     ];
-    assert_eq!(search_diagonal(input, &Regex::new(r"XMAS").unwrap(), &Regex::new(r"SAMX").unwrap()), 5);
+    let word_search = WordSearch::new(&["XMAS"]).directions(&DIAGONALS);
+    assert_eq!(word_search.count(&Grid::parse(&input.join("\n"))), 5);
   }
 }
 
@@ -365,9 +217,64 @@ mod part2 {
 
   #[test]
   fn provided_test_case_should_pass() {
+    let grid = parse_grid(
+      ".M.S......
+..A..MSMS.
+.M.S.MAA..
+..A.ASMSM.
+.M.S.M....
+..........
+S.S.S.S.S.
+.A.A.A.A..
+M.M.M.M.M.
+..........
+",
+    )
+    .unwrap();
+
+    assert_eq!(part2(&grid), 9);
+  }
+}
+
+#[cfg(test)]
+mod render_tests {
+  use super::*;
+
+  const EXAMPLE: &str = "MMMSXXMASM
+MSAMXMSMSA
+AMXSXMAAMM
+MSAMASMSMX
+XMASAMXAMM
+XXAMMXXAMA
+SMSMSASXSS
+SAXAMASAAA
+MAMMMXMMMM
+MXMXAXMASX
+";
+
+  #[test]
+  fn part1_render_matches_the_puzzle_text() {
     assert_eq!(
-      part2(
-        ".M.S......
+      render_part1(&parse_grid(EXAMPLE).unwrap(), false),
+      "....XXMAS.
+.SAMXMS...
+...S..A...
+..A.A.MS.X
+XMASAMX.MM
+X.....XA.A
+S.S.S.S.SS
+.A.A.A.A.A
+..M.M.M.MM
+.X.X.XMASX
+"
+    );
+  }
+
+  #[test]
+  fn part2_render_matches_the_puzzle_text() {
+    assert_eq!(
+      render_part2(&parse_grid(EXAMPLE).unwrap(), false),
+      ".M.S......
 ..A..MSMS.
 .M.S.MAA..
 ..A.ASMSM.
@@ -378,8 +285,13 @@ S.S.S.S.S.
 M.M.M.M.M.
 ..........
 "
-      ),
-      9
     );
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn colour_output_wraps_highlighted_letters() {
+    let rendered = render_part1(&parse_grid("XMAS").unwrap(), true);
+
+    assert_eq!(rendered, "\x1b[33mX\x1b[0m\x1b[33mM\x1b[0m\x1b[33mA\x1b[0m\x1b[33mS\x1b[0m\n");
+  }
+}
@@ -1,69 +1,219 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+
+use crate::parse_error::{parse_number, ParseError, ParseErrorKind};
+use crate::solve_error::Overflow;
+
+const DAY: u8 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Day5Error {
+  Parse(ParseError),
+  Cycle(RuleCycle),
+  UnknownUpdate { update: usize, updates: usize },
+  Overflow(Overflow),
+}
+
+impl fmt::Display for Day5Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Day5Error::Parse(error) => error.fmt(f),
+      Day5Error::Cycle(cycle) => cycle.fmt(f),
+      Day5Error::UnknownUpdate { update, updates } => {
+        write!(f, "there is no update #{}, the input has {} updates", update, updates)
+      }
+      Day5Error::Overflow(error) => error.fmt(f),
+    }
+  }
+}
+
+impl Error for Day5Error {}
+
+impl From<ParseError> for Day5Error {
+  fn from(error: ParseError) -> Self {
+    Day5Error::Parse(error)
+  }
+}
+
+impl From<Overflow> for Day5Error {
+  fn from(error: Overflow) -> Self {
+    Day5Error::Overflow(error)
+  }
+}
+
+#[aoc_generator(day5)]
+pub fn parse_input(input: &str) -> Result<RulesAndPrintOrders, ParseError> {
+  let mut parsing_rules = true;
+  let mut rules: HashMap<i32, Rule> = HashMap::new();
+  let mut rule_lines: HashMap<(i32, i32), usize> = HashMap::new();
+  let mut print_orders: Vec<PrintOrder> = Vec::new();
+  for (line_index, line) in input.lines().enumerate() {
+    let line_number = line_index + 1;
+    if line.is_empty() {
+      parsing_rules = false;
+      continue;
+    }
+
+    if parsing_rules {
+      let (page_before, page_after) = parse_rule(line_number, line)?;
+      rule_lines.entry((page_before, page_after)).or_insert(line_number);
+      if let Some(current_entry) = rules.get_mut(&page_before) {
+        current_entry.pages_after.push(page_after);
+      } else {
+        rules.insert(page_before, Rule {
+          pages_before: Vec::new(),
+          pages_after: vec![page_after],
+        });
+      }
+
+      if let Some(current_entry) = rules.get_mut(&page_after) {
+        current_entry.pages_before.push(page_before);
+      } else {
+        rules.insert(page_after, Rule {
+          pages_before: vec![page_before],
+          pages_after: Vec::new(),
+        });
+      }
+    } else {
+      print_orders.push(parse_print_order(line_number, line)?);
+    }
+  }
+
+  Ok(RulesAndPrintOrders {
+    print_orders,
+    rules,
+    rule_lines,
+  })
+}
 
 #[aoc(day5, part1, Chars)]
-pub fn part1(input: &str) -> i32 {
-  let mut total = 0i32;
-  let rule_and_print_orders = parse_input(input);
+pub fn part1(rule_and_print_orders: &RulesAndPrintOrders) -> Result<i64, Overflow> {
+  let mut total = 0i64;
 
-  for print_order in rule_and_print_orders.print_orders {
+  for print_order in &rule_and_print_orders.print_orders {
     if is_print_order_correct(&print_order.original_order, &rule_and_print_orders.rules) {
-      total += print_order.get_middle_value();
+      total = add_middle_value(total, print_order)?;
     }
   }
 
-  total
+  Ok(total)
 }
 
 #[aoc(day5, part2, Chars)]
-pub fn part2(input: &str) -> i32 {
-  let mut total = 0i32;
-  let rule_and_print_orders = parse_input(input);
+pub fn part2(rule_and_print_orders: &RulesAndPrintOrders) -> Result<i64, Day5Error> {
+  sum_corrected_middle_values(rule_and_print_orders, build_correct_order_from_rules)
+}
 
-  for print_order in rule_and_print_orders.print_orders {
+#[aoc(day5, part2, SwapSort)]
+pub fn part2_swap_sort(rule_and_print_orders: &RulesAndPrintOrders) -> Result<i64, Day5Error> {
+  // swap_sort can't put a cycle in order, so it has to be ruled out first
+  sum_corrected_middle_values(rule_and_print_orders, |rules, print_order| match find_cycle(rules, &print_order.original_order) {
+    Some(pages) => Err(pages),
+    None => Ok(swap_sort(rules, print_order)),
+  })
+}
+
+// `correct` fails with the pages of a cycle when the rules leave no valid order
+fn sum_corrected_middle_values(
+  rule_and_print_orders: &RulesAndPrintOrders,
+  correct: impl Fn(&HashMap<i32, Rule>, &PrintOrder) -> Result<PrintOrder, Vec<i32>>,
+) -> Result<i64, Day5Error> {
+  let mut total = 0i64;
+
+  for (index, print_order) in rule_and_print_orders.print_orders.iter().enumerate() {
     if !is_print_order_correct(&print_order.original_order, &rule_and_print_orders.rules) {
-      let corrected_print_order = build_correct_order_from_rules(&rule_and_print_orders.rules, &print_order);
-      total += corrected_print_order.get_middle_value();
+      let corrected_print_order = correct(&rule_and_print_orders.rules, print_order)
+        .map_err(|pages| Day5Error::Cycle(RuleCycle { update: Some(index + 1), pages }))?;
+      total = add_middle_value(total, &corrected_print_order)?;
     }
   }
 
+  Ok(total)
+}
+
+fn add_middle_value(total: i64, print_order: &PrintOrder) -> Result<i64, Overflow> {
   total
+    .checked_add(i64::from(print_order.get_middle_value()))
+    .ok_or(Overflow::new(DAY, "sum of middle pages"))
 }
 
-fn build_correct_order_from_rules(rules: &HashMap<i32, Rule>, print_order: &PrintOrder) -> PrintOrder {
-  let mut corrected_order: Vec<i32> = print_order.original_order.clone();
+// Kahn's algorithm over the rules restricted to the pages of this update.
+// Whenever several pages are free the one earliest in the original update goes next, so pages the rules
+// don't order keep their original order and swap_sort ends up with the same result.
+// If it stalls, the pages it couldn't place contain a cycle, which is returned instead.
+fn build_correct_order_from_rules(rules: &HashMap<i32, Rule>, print_order: &PrintOrder) -> Result<PrintOrder, Vec<i32>> {
+  let pages = &print_order.original_order;
+  let positions: HashMap<i32, usize> = pages.iter().enumerate().map(|(position, page)| (*page, position)).collect();
 
-  while !is_print_order_correct(&corrected_order, rules) {
-    let swapped = false;
-    for i in 0..corrected_order.len() {
-      if swapped {
-        break;
-      }
-      let page_number = corrected_order[i];
-      for j in (i + 1)..corrected_order.len() {
-        if swapped {
-          break;
-        }
-        let page_number_after = corrected_order[j];
-        if let Some(rule) = rules.get(&page_number) {
-          if rule.pages_before.contains(&page_number_after) {
-            corrected_order.swap(i, j);
-          }
+  let mut pages_after: Vec<Vec<usize>> = vec![Vec::new(); pages.len()];
+  let mut unplaced_pages_before: Vec<usize> = vec![0; pages.len()];
+  for (position, page_number) in pages.iter().enumerate() {
+    if let Some(rule) = rules.get(page_number) {
+      for page_number_after in &rule.pages_after {
+        if let Some(position_after) = positions.get(page_number_after) {
+          pages_after[position].push(*position_after);
+          unplaced_pages_before[*position_after] += 1;
         }
       }
     }
   }
 
-  PrintOrder {
+  let mut ready: BinaryHeap<Reverse<usize>> =
+    (0..pages.len()).filter(|position| unplaced_pages_before[*position] == 0).map(Reverse).collect();
+  let mut corrected_order: Vec<i32> = Vec::with_capacity(pages.len());
+  while let Some(Reverse(position)) = ready.pop() {
+    corrected_order.push(pages[position]);
+    for position_after in &pages_after[position] {
+      unplaced_pages_before[*position_after] -= 1;
+      if unplaced_pages_before[*position_after] == 0 {
+        ready.push(Reverse(*position_after));
+      }
+    }
+  }
+
+  if corrected_order.len() < pages.len() {
+    let unplaced_pages = (0..pages.len())
+      .filter(|position| unplaced_pages_before[*position] > 0)
+      .map(|position| pages[position])
+      .collect::<Vec<i32>>();
+    return Err(find_cycle(rules, &unplaced_pages).unwrap_or(unplaced_pages));
+  }
+
+  Ok(PrintOrder {
     original_order: corrected_order
+  })
+}
+
+// Selection sort with adjacent swaps: the first unsorted page that no other unsorted page has to come before is
+// swapped down to the end of the sorted part. That shifts the pages it passes without reordering them, so it
+// breaks ties the same way as build_correct_order_from_rules, but rescans the unsorted pages for every position.
+// With a cycle no page is ever free and the cycle's pages are left where they are.
+fn swap_sort(rules: &HashMap<i32, Rule>, print_order: &PrintOrder) -> PrintOrder {
+  let mut pages = print_order.original_order.clone();
+  for sorted in 0..pages.len() {
+    let unsorted = &pages[sorted..];
+    let free = unsorted
+      .iter()
+      .position(|page| rules.get(page).is_none_or(|rule| !rule.pages_before.iter().any(|page_before| unsorted.contains(page_before))));
+    if let Some(free) = free {
+      for position in (sorted..sorted + free).rev() {
+        pages.swap(position, position + 1);
+      }
+    }
   }
+
+  PrintOrder { original_order: pages }
 }
 
 fn is_print_order_correct(print_order: &Vec<i32>, rules: &HashMap<i32, Rule>) -> bool {
   let print_order_pages_set: HashSet<i32> = HashSet::from_iter(print_order.iter().cloned());
   let mut visited_page_numbers: HashSet<i32> = HashSet::new();
   for page_number in print_order {
-    if let Some(rule) = rules.get(&page_number) {
+    if let Some(rule) = rules.get(page_number) {
       for page_number_after in &rule.pages_after {
         if visited_page_numbers.contains(page_number_after) {
           // Exit early if the page is supposed to be after but appears before
@@ -78,104 +228,291 @@ fn is_print_order_correct(print_order: &Vec<i32>, rules: &HashMap<i32, Rule>) ->
         }
       }
     };
-    visited_page_numbers.insert(page_number.clone());
+    visited_page_numbers.insert(*page_number);
   }
 
   true
 }
 
-struct RulesAndPrintOrders {
-  rules: HashMap<i32, Rule>,
-  print_orders: Vec<PrintOrder>,
+// A rule `page_before|page_after` that an update breaks by printing page_after first.
+// Positions are 0-based indexes into the update.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleViolation {
+  pub page_before: i32,
+  pub page_after: i32,
+  pub position_before: usize,
+  pub position_after: usize,
+  pub rule_line: Option<usize>,
 }
 
-struct Rule {
-  pages_before: Vec<i32>,
-  pages_after: Vec<i32>,
+impl fmt::Display for RuleViolation {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "rule {}|{}", self.page_before, self.page_after)?;
+    if let Some(rule_line) = self.rule_line {
+      write!(f, " (line {})", rule_line)?;
+    }
+    write!(
+      f,
+      " is broken: {} at index {} is printed before {} at index {}",
+      self.page_after, self.position_after, self.page_before, self.position_before
+    )
+  }
 }
 
-struct PrintOrder {
-  original_order: Vec<i32>,
+// Every violated rule for every update, in update order. An empty list means the update is correct.
+pub fn explain_updates(rules_and_print_orders: &RulesAndPrintOrders) -> Vec<Vec<RuleViolation>> {
+  rules_and_print_orders
+    .print_orders
+    .iter()
+    .map(|print_order| {
+      find_violations(&print_order.original_order, &rules_and_print_orders.rules, &rules_and_print_orders.rule_lines)
+    })
+    .collect()
 }
 
-trait GetMiddleValue {
-  fn get_middle_value(&self) -> i32;
+// Unlike is_print_order_correct this keeps going after the first problem so every broken rule is listed
+fn find_violations(print_order: &[i32], rules: &HashMap<i32, Rule>, rule_lines: &HashMap<(i32, i32), usize>) -> Vec<RuleViolation> {
+  let mut violations = Vec::new();
+
+  for (position_after, page_after) in print_order.iter().enumerate() {
+    let Some(rule) = rules.get(page_after) else {
+      continue;
+    };
+    for (position_before, page_before) in print_order.iter().enumerate().skip(position_after + 1) {
+      if rule.pages_before.contains(page_before) {
+        violations.push(RuleViolation {
+          page_before: *page_before,
+          page_after: *page_after,
+          position_before,
+          position_after,
+          rule_line: rule_lines.get(&(*page_before, *page_after)).copied(),
+        });
+      }
+    }
+  }
+
+  violations
 }
 
-impl GetMiddleValue for PrintOrder {
-  fn get_middle_value(&self) -> i32 {
-    let length = self.original_order.len();
-    if length % 2 == 0 {
-      // To be clear we should NEVER panic in production
-      panic!("Print order length is even");
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+  Dot,
+  Mermaid,
+}
+
+// The rule graph as Graphviz DOT or a Mermaid flowchart, one edge per `before|after` rule in input order.
+// With an update (1-based) only that update's pages are drawn and the rules it violates are coloured red.
+pub fn export_rule_graph(rules_and_print_orders: &RulesAndPrintOrders, format: GraphFormat, update: Option<usize>) -> Result<String, Day5Error> {
+  let (pages, violations) = match update {
+    Some(update) => {
+      let updates = rules_and_print_orders.print_orders.len();
+      let Some(print_order) = update.checked_sub(1).and_then(|index| rules_and_print_orders.print_orders.get(index)) else {
+        return Err(Day5Error::UnknownUpdate { update, updates });
+      };
+      let violations = find_violations(&print_order.original_order, &rules_and_print_orders.rules, &rules_and_print_orders.rule_lines);
+      (print_order.original_order.clone(), violations)
+    }
+    None => {
+      let mut pages = rules_and_print_orders.rules.keys().copied().collect::<Vec<i32>>();
+      pages.sort();
+      (pages, Vec::new())
     }
+  };
 
-    let middle = length / 2;
-    self.original_order[middle]
+  let pages_set: HashSet<i32> = pages.iter().copied().collect();
+  let violated: HashSet<(i32, i32)> = violations.iter().map(|violation| (violation.page_before, violation.page_after)).collect();
+  let mut edges = rules_and_print_orders
+    .rule_lines
+    .iter()
+    .filter(|((page_before, page_after), _)| pages_set.contains(page_before) && pages_set.contains(page_after))
+    .map(|(edge, line)| (*line, *edge))
+    .collect::<Vec<(usize, (i32, i32))>>();
+  edges.sort();
+
+  let mut output = String::new();
+  match format {
+    GraphFormat::Dot => {
+      output.push_str("digraph rules {\n");
+      for page in &pages {
+        output.push_str(&format!("  {};\n", page));
+      }
+      for (_, edge) in &edges {
+        let style = if violated.contains(edge) { " [color=red, penwidth=2]" } else { "" };
+        output.push_str(&format!("  {} -> {}{};\n", edge.0, edge.1, style));
+      }
+      output.push_str("}\n");
+    }
+    GraphFormat::Mermaid => {
+      output.push_str("flowchart LR\n");
+      for page in &pages {
+        output.push_str(&format!("  p{}[{}]\n", page, page));
+      }
+      for (_, edge) in &edges {
+        output.push_str(&format!("  p{} --> p{}\n", edge.0, edge.1));
+      }
+      // linkStyle refers to edges by the order they were declared in
+      let violated_links = edges
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, edge))| violated.contains(edge))
+        .map(|(index, _)| index.to_string())
+        .collect::<Vec<String>>();
+      if !violated_links.is_empty() {
+        output.push_str(&format!("  linkStyle {} stroke:red,stroke-width:2px\n", violated_links.join(",")));
+      }
+    }
   }
+
+  Ok(output)
 }
 
-fn parse_input(input: &str) -> RulesAndPrintOrders {
-  let mut parsing_rules = true;
-  let mut rules: HashMap<i32, Rule> = HashMap::new();
-  let mut print_orders: Vec<PrintOrder> = Vec::new();
-  for line in input.lines() {
-    if line.is_empty() {
-      parsing_rules = false;
+// `pages` is closed, so the first page is repeated at the end.
+// `update` is the 1-based position of the update the cycle was found in, or None for the full rule graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleCycle {
+  pub update: Option<usize>,
+  pub pages: Vec<i32>,
+}
+
+impl fmt::Display for RuleCycle {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let pages = self.pages.iter().map(|page| page.to_string()).collect::<Vec<String>>();
+    write!(f, "cycle {}", pages.join(" \u{2192} "))?;
+    match self.update {
+      Some(update) => write!(f, " in update #{}", update),
+      None => write!(f, " in the page ordering rules"),
+    }
+  }
+}
+
+// One cycle from the full rule graph (if any) followed by one cycle per update whose pages contain one
+pub fn find_cycles(rules_and_print_orders: &RulesAndPrintOrders) -> Vec<RuleCycle> {
+  let mut cycles = Vec::new();
+
+  let mut all_pages = rules_and_print_orders.rules.keys().copied().collect::<Vec<i32>>();
+  all_pages.sort();
+  if let Some(pages) = find_cycle(&rules_and_print_orders.rules, &all_pages) {
+    cycles.push(RuleCycle { update: None, pages });
+  }
+
+  for (index, print_order) in rules_and_print_orders.print_orders.iter().enumerate() {
+    if let Some(pages) = find_cycle(&rules_and_print_orders.rules, &print_order.original_order) {
+      cycles.push(RuleCycle { update: Some(index + 1), pages });
+    }
+  }
+
+  cycles
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum VisitState {
+  InProgress,
+  Done,
+}
+
+// Depth first search over the rules restricted to `pages`, starting from each page in turn.
+// Returns the first cycle found as a closed page sequence.
+fn find_cycle(rules: &HashMap<i32, Rule>, pages: &[i32]) -> Option<Vec<i32>> {
+  let pages_set: HashSet<i32> = pages.iter().copied().collect();
+  let mut visit_states: HashMap<i32, VisitState> = HashMap::new();
+
+  for start in pages {
+    if visit_states.contains_key(start) {
       continue;
     }
 
-    if parsing_rules {
-      let (page_before, page_after) = parse_rule(line);
-      if let Some(current_entry) = rules.get_mut(&page_before) {
-        current_entry.pages_after.push(page_after);
-      } else {
-        rules.insert(page_before, Rule {
-          pages_before: Vec::new(),
-          pages_after: vec![page_after],
-        });
+    // Each entry is a page and how many of its pages_after have been followed so far
+    let mut stack: Vec<(i32, usize)> = vec![(*start, 0)];
+    visit_states.insert(*start, VisitState::InProgress);
+
+    while let Some((page_number, followed)) = stack.last().copied() {
+      let pages_after = rules.get(&page_number).map_or(&[][..], |rule| &rule.pages_after[..]);
+      let Some(page_number_after) = pages_after.get(followed).copied() else {
+        visit_states.insert(page_number, VisitState::Done);
+        stack.pop();
+        continue;
+      };
+
+      if let Some(top) = stack.last_mut() {
+        top.1 += 1;
+      }
+      if !pages_set.contains(&page_number_after) {
+        continue;
       }
 
-      if let Some(current_entry) = rules.get_mut(&page_after) {
-        current_entry.pages_before.push(page_before);
-      } else {
-        rules.insert(page_after, Rule {
-          pages_before: vec![page_before],
-          pages_after: Vec::new(),
-        });
+      match visit_states.get(&page_number_after) {
+        Some(VisitState::InProgress) => {
+          let cycle_start = stack.iter().position(|(page, _)| *page == page_number_after).unwrap_or(0);
+          let mut cycle = stack[cycle_start..].iter().map(|(page, _)| *page).collect::<Vec<i32>>();
+          cycle.push(page_number_after);
+          return Some(cycle);
+        }
+        Some(VisitState::Done) => {}
+        None => {
+          visit_states.insert(page_number_after, VisitState::InProgress);
+          stack.push((page_number_after, 0));
+        }
       }
-    } else {
-      print_orders.push(parse_print_order(line));
     }
   }
 
-  RulesAndPrintOrders {
-    print_orders,
-    rules,
+  None
+}
+
+pub struct RulesAndPrintOrders {
+  rules: HashMap<i32, Rule>,
+  // Line each `before|after` rule was first declared on
+  rule_lines: HashMap<(i32, i32), usize>,
+  print_orders: Vec<PrintOrder>,
+}
+
+struct Rule {
+  pages_before: Vec<i32>,
+  pages_after: Vec<i32>,
+}
+
+#[derive(Debug)]
+struct PrintOrder {
+  original_order: Vec<i32>,
+}
+
+trait GetMiddleValue {
+  fn get_middle_value(&self) -> i32;
+}
+
+// parse_print_order only accepts an odd number of pages, and reordering keeps the length, so there is always a middle
+impl GetMiddleValue for PrintOrder {
+  fn get_middle_value(&self) -> i32 {
+    let middle = self.original_order.len() / 2;
+    self.original_order[middle]
   }
 }
 
-fn parse_rule(line: &str) -> (i32, i32) {
-  let mut parts = line.split("|");
-  let page_before = parts.next().unwrap().parse::<i32>().unwrap();
-  let page_after = parts.next().unwrap().parse::<i32>().unwrap();
+fn parse_rule(line_number: usize, line: &str) -> Result<(i32, i32), ParseError> {
+  let Some((before, after)) = line.split_once("|") else {
+    return Err(ParseError::new(DAY, line_number, 1, line, ParseErrorKind::MissingSeparator("|")));
+  };
+  let page_before = parse_number(DAY, line_number, line, before)?;
+  let page_after = parse_number(DAY, line_number, line, after)?;
 
-  (
+  Ok((
     page_before,
     page_after,
-  )
+  ))
 }
 
-fn parse_print_order(line: &str) -> PrintOrder {
+fn parse_print_order(line_number: usize, line: &str) -> Result<PrintOrder, ParseError> {
   let parts = line
     .split(",")
-    .map(|s| s.parse::<i32>().unwrap())
-    .collect();
-
+    .map(|s| parse_number(DAY, line_number, line, s))
+    .collect::<Result<Vec<i32>, ParseError>>()?;
+  if parts.len().is_multiple_of(2) {
+    return Err(ParseError::new(DAY, line_number, 1, line, ParseErrorKind::EvenLength(parts.len())));
+  }
 
-  PrintOrder {
+  Ok(PrintOrder {
     original_order: parts,
-  }
+  })
 }
 
 #[cfg(test)]
@@ -184,11 +521,26 @@ mod parse_print_order {
 
   #[test]
   fn parses_print_order() {
-    let input = "1,2,3,4,5,6,7,8,9,10";
+    let input = "1,2,3,4,5,6,7,8,9,10,11";
+
+    let print_order = parse_print_order(1, input).unwrap();
+
+    assert_eq!(print_order.original_order, vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
+  }
 
-    let print_order = parse_print_order(input);
+  #[test]
+  fn even_length_is_an_error() {
+    let error = parse_print_order(12, "1,2,3,4").unwrap_err();
+
+    assert_eq!(error, ParseError::new(DAY, 12, 1, "1,2,3,4", ParseErrorKind::EvenLength(4)));
+    assert_eq!(error.to_string(), "day 5, line 12, column 1: expected an odd number of pages but found 4: `1,2,3,4`");
+  }
+
+  #[test]
+  fn invalid_page_reports_column() {
+    let error = parse_print_order(30, "75,47,6l,53,29").unwrap_err();
 
-    assert_eq!(print_order.original_order, vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+    assert_eq!(error, ParseError::new(DAY, 30, 7, "6l", ParseErrorKind::InvalidNumber));
   }
 }
 
@@ -200,11 +552,18 @@ mod parse_rule {
   fn parses_rule() {
     let input = "11|12";
 
-    let (page_before, page_after) = parse_rule(input);
+    let (page_before, page_after) = parse_rule(1, input).unwrap();
 
     assert_eq!(page_before, 11);
     assert_eq!(page_after, 12);
   }
+
+  #[test]
+  fn missing_separator_is_an_error() {
+    let error = parse_rule(4, "11,12").unwrap_err();
+
+    assert_eq!(error, ParseError::new(DAY, 4, 1, "11,12", ParseErrorKind::MissingSeparator("|")));
+  }
 }
 
 #[cfg(test)]
@@ -213,13 +572,13 @@ mod parse_input {
 
   #[test]
   fn parses_input() {
-    let input = "11|12\n11|13\n13|12\n\n1,2,3,4,5,6,7,8,9,10\n11,12,13,14,15,16,17,18,19,20";
+    let input = "11|12\n11|13\n13|12\n\n1,2,3,4,5,6,7,8,9\n11,12,13,14,15,16,17,18,19";
 
-    let rules_and_print_orders = parse_input(input);
+    let rules_and_print_orders = parse_input(input).unwrap();
 
     assert_eq!(rules_and_print_orders.print_orders.len(), 2);
-    assert_eq!(rules_and_print_orders.print_orders[0].original_order, vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
-    assert_eq!(rules_and_print_orders.print_orders[1].original_order, vec![11, 12, 13, 14, 15, 16, 17, 18, 19, 20]);
+    assert_eq!(rules_and_print_orders.print_orders[0].original_order, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    assert_eq!(rules_and_print_orders.print_orders[1].original_order, vec![11, 12, 13, 14, 15, 16, 17, 18, 19]);
     assert_eq!(rules_and_print_orders.rules.len(), 3);
     assert_eq!(rules_and_print_orders.rules.get(&11).unwrap().pages_before, vec![]);
     assert_eq!(rules_and_print_orders.rules.get(&11).unwrap().pages_after, vec![12, 13]);
@@ -244,13 +603,18 @@ mod print_order_tests {
   }
 
   #[test]
-  #[should_panic]
-  fn get_middle_value_even() {
-    let print_order = PrintOrder {
-      original_order: vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10],
-    };
+  fn middle_values_past_i32_and_overflow_at_i64() {
+    let print_order = PrintOrder { original_order: vec![1, 2, 3] };
 
-    print_order.get_middle_value();
+    assert_eq!(add_middle_value(i64::from(i32::MAX), &print_order), Ok(2_147_483_649));
+    assert_eq!(add_middle_value(i64::MAX, &print_order), Err(Overflow::new(DAY, "sum of middle pages")));
+  }
+
+  #[test]
+  fn even_length_update_is_a_parse_error_not_a_panic() {
+    let error = parse_input("1|2\n\n1,2\n").err().unwrap();
+
+    assert_eq!(error, ParseError::new(DAY, 3, 1, "1,2", ParseErrorKind::EvenLength(2)));
   }
 }
 
@@ -272,9 +636,9 @@ mod part1_tests {
 2,3,4
 3,4,5";
 
-    let result = part1(input);
+    let result = part1(&parse_input(input).unwrap());
 
-    assert_eq!(result, 3 + 3 + 3 + 4);
+    assert_eq!(result, Ok(3 + 3 + 3 + 4));
   }
 
   #[test]
@@ -308,9 +672,9 @@ mod part1_tests {
 61,13,29
 97,13,75,29,47";
 
-    let result = part1(input);
+    let result = part1(&parse_input(input).unwrap());
 
-    assert_eq!(result, 143);
+    assert_eq!(result, Ok(143));
   }
 }
 
@@ -346,7 +710,49 @@ mod build_correct_order_from_rules_tests {
       original_order: vec![5, 3, 4, 1, 2],
     };
 
-    assert_eq!(build_correct_order_from_rules(&rules, &print_order).original_order, vec![1, 2, 3, 4, 5]);
+    assert_eq!(build_correct_order_from_rules(&rules, &print_order).unwrap().original_order, vec![1, 2, 3, 4, 5]);
+  }
+
+  #[test]
+  fn ties_keep_the_original_order() {
+    let rules = HashMap::from([
+      (9, Rule {
+        pages_before: vec![],
+        pages_after: vec![1],
+      }),
+      (1, Rule {
+        pages_before: vec![9],
+        pages_after: vec![],
+      }),
+    ]);
+    let print_order = PrintOrder {
+      original_order: vec![4, 1, 9, 2],
+    };
+
+    assert_eq!(build_correct_order_from_rules(&rules, &print_order).unwrap().original_order, vec![4, 9, 1, 2]);
+  }
+
+  #[test]
+  fn cycle_is_an_error() {
+    let rules = HashMap::from([
+      (1, Rule {
+        pages_before: vec![3],
+        pages_after: vec![2],
+      }),
+      (2, Rule {
+        pages_before: vec![1],
+        pages_after: vec![3],
+      }),
+      (3, Rule {
+        pages_before: vec![2],
+        pages_after: vec![1],
+      }),
+    ]);
+    let print_order = PrintOrder {
+      original_order: vec![4, 3, 2, 1],
+    };
+
+    assert_eq!(build_correct_order_from_rules(&rules, &print_order).unwrap_err(), vec![3, 1, 2, 3]);
   }
 
   #[test]
@@ -386,6 +792,268 @@ mod build_correct_order_from_rules_tests {
       original_order: vec![37, 75, 61, 65, 31, 47, 87],
     };
 
-    assert_eq!(build_correct_order_from_rules(&rules, &print_order).original_order, vec![65, 47, 37, 75, 61, 87, 31]);
+    assert_eq!(build_correct_order_from_rules(&rules, &print_order).unwrap().original_order, vec![65, 47, 37, 75, 61, 87, 31]);
+  }
+}
+
+#[cfg(test)]
+mod swap_sort_tests {
+  use super::*;
+  use proptest::prelude::*;
+
+  const EXAMPLE: &str = "47|53
+97|13
+97|61
+97|47
+75|29
+61|13
+75|53
+29|13
+97|29
+53|29
+61|53
+97|53
+61|29
+47|13
+75|47
+97|75
+47|61
+75|61
+47|29
+75|13
+53|13
+
+75,47,61,53,29
+97,61,53,29,13
+75,29,13
+75,97,47,61,53
+61,13,29
+97,13,75,29,47";
+
+  #[test]
+  fn matches_kahns_algorithm_on_each_update_of_the_example() {
+    let rules_and_print_orders = parse_input(EXAMPLE).unwrap();
+
+    for print_order in &rules_and_print_orders.print_orders {
+      let swapped = swap_sort(&rules_and_print_orders.rules, print_order);
+      let sorted = build_correct_order_from_rules(&rules_and_print_orders.rules, print_order).unwrap();
+
+      assert_eq!(swapped.original_order, sorted.original_order);
+    }
+  }
+
+  #[test]
+  fn both_part2_variants_give_the_provided_answer() {
+    let rules_and_print_orders = parse_input(EXAMPLE).unwrap();
+
+    assert_eq!(part2(&rules_and_print_orders), Ok(123));
+    assert_eq!(part2_swap_sort(&rules_and_print_orders), Ok(123));
+  }
+
+  #[test]
+  fn matches_kahns_algorithm_when_the_rules_leave_pages_unordered() {
+    let rules_and_print_orders = parse_input("1|3\n\n3,2,1").unwrap();
+    let print_order = &rules_and_print_orders.print_orders[0];
+
+    assert_eq!(swap_sort(&rules_and_print_orders.rules, print_order).original_order, vec![2, 1, 3]);
+    assert_eq!(build_correct_order_from_rules(&rules_and_print_orders.rules, print_order).unwrap().original_order, vec![2, 1, 3]);
+    assert_eq!(part2_swap_sort(&rules_and_print_orders), part2(&rules_and_print_orders));
+  }
+
+  proptest! {
+    // Rules only go from a smaller page to a larger one, so they never form a cycle but often leave pages unordered
+    #[test]
+    fn matches_kahns_algorithm_on_any_acyclic_rules(
+      rules in proptest::collection::btree_set((1..8i32, 1..8i32).prop_filter("ascending", |(a, b)| a < b), 0..12),
+      update in Just((1..8).collect::<Vec<i32>>()).prop_shuffle(),
+    ) {
+      let rules = rules.iter().map(|(before, after)| format!("{}|{}", before, after)).collect::<Vec<String>>();
+      let update = update.iter().map(|page| page.to_string()).collect::<Vec<String>>();
+      let rules_and_print_orders = parse_input(&format!("{}\n\n{}", rules.join("\n"), update.join(","))).unwrap();
+      let print_order = &rules_and_print_orders.print_orders[0];
+
+      prop_assert_eq!(
+        swap_sort(&rules_and_print_orders.rules, print_order).original_order,
+        build_correct_order_from_rules(&rules_and_print_orders.rules, print_order).unwrap().original_order
+      );
+    }
+  }
+}
+
+#[cfg(test)]
+mod find_cycles_tests {
+  use super::*;
+
+  const CYCLIC_INPUT: &str = "47|53
+53|29
+29|47
+97|13
+
+97,13,61
+75,47,61
+61,13,29
+75,97,47,61,53
+47,53,29";
+
+  #[test]
+  fn finds_cycles_in_the_rules_and_in_each_update() {
+    let cycles = find_cycles(&parse_input(CYCLIC_INPUT).unwrap());
+
+    assert_eq!(
+      cycles,
+      vec![
+        RuleCycle { update: None, pages: vec![29, 47, 53, 29] },
+        RuleCycle { update: Some(5), pages: vec![47, 53, 29, 47] },
+      ]
+    );
+  }
+
+  #[test]
+  fn acyclic_rules_have_no_cycles() {
+    let cycles = find_cycles(&parse_input("47|53\n53|29\n\n47,53,29").unwrap());
+
+    assert_eq!(cycles, vec![]);
+  }
+
+  #[test]
+  fn cycle_reads_as_a_page_sequence() {
+    let cycle = RuleCycle { update: Some(4), pages: vec![47, 53, 29, 47] };
+
+    assert_eq!(cycle.to_string(), "cycle 47 \u{2192} 53 \u{2192} 29 \u{2192} 47 in update #4");
+  }
+
+  #[test]
+  fn part2_reports_the_cycle_instead_of_looping() {
+    let expected = Err(Day5Error::Cycle(RuleCycle { update: Some(5), pages: vec![47, 53, 29, 47] }));
+
+    assert_eq!(part2(&parse_input(CYCLIC_INPUT).unwrap()), expected);
+    assert_eq!(part2_swap_sort(&parse_input(CYCLIC_INPUT).unwrap()), expected);
+  }
+}
+
+#[cfg(test)]
+mod explain_updates_tests {
+  use super::*;
+
+  const INPUT: &str = "47|53
+97|13
+97|61
+97|47
+75|29
+61|13
+75|53
+29|13
+97|29
+53|29
+61|53
+97|53
+61|29
+47|13
+75|47
+97|75
+47|61
+75|61
+47|29
+75|13
+53|13
+
+75,47,61,53,29
+97,61,53,29,13
+75,29,13
+75,97,47,61,53
+61,13,29
+97,13,75,29,47";
+
+  #[test]
+  fn correct_updates_have_no_violations() {
+    let explanations = explain_updates(&parse_input(INPUT).unwrap());
+
+    assert_eq!(explanations[0], vec![]);
+    assert_eq!(explanations[1], vec![]);
+    assert_eq!(explanations[2], vec![]);
+  }
+
+  #[test]
+  fn lists_every_violated_rule_with_its_line() {
+    let explanations = explain_updates(&parse_input(INPUT).unwrap());
+
+    assert_eq!(
+      explanations[4],
+      vec![RuleViolation {
+        page_before: 29,
+        page_after: 13,
+        position_before: 2,
+        position_after: 1,
+        rule_line: Some(8),
+      }]
+    );
+    assert_eq!(explanations[5].len(), 4);
+    assert_eq!(
+      explanations[3][0].to_string(),
+      "rule 97|75 (line 16) is broken: 75 at index 0 is printed before 97 at index 1"
+    );
+  }
+
+  #[test]
+  fn reordered_updates_have_no_violations() {
+    let rules_and_print_orders = parse_input(INPUT).unwrap();
+
+    for print_order in &rules_and_print_orders.print_orders {
+      let corrected = build_correct_order_from_rules(&rules_and_print_orders.rules, print_order).unwrap();
+      let violations = find_violations(&corrected.original_order, &rules_and_print_orders.rules, &rules_and_print_orders.rule_lines);
+
+      assert_eq!(violations, vec![]);
+    }
+  }
+}
+
+#[cfg(test)]
+mod export_rule_graph_tests {
+  use super::*;
+
+  const INPUT: &str = "47|53
+97|13
+53|13
+
+47,53,13
+13,97,47";
+
+  #[test]
+  fn exports_the_full_graph_as_dot() {
+    assert_eq!(
+      export_rule_graph(&parse_input(INPUT).unwrap(), GraphFormat::Dot, None).unwrap(),
+      "digraph rules {
+  13;
+  47;
+  53;
+  97;
+  47 -> 53;
+  97 -> 13;
+  53 -> 13;
+}
+"
+    );
+  }
+
+  #[test]
+  fn exports_one_update_as_mermaid_with_violations_highlighted() {
+    assert_eq!(
+      export_rule_graph(&parse_input(INPUT).unwrap(), GraphFormat::Mermaid, Some(2)).unwrap(),
+      "flowchart LR
+  p13[13]
+  p97[97]
+  p47[47]
+  p97 --> p13
+  linkStyle 0 stroke:red,stroke-width:2px
+"
+    );
+  }
+
+  #[test]
+  fn unknown_update_is_an_error() {
+    assert_eq!(
+      export_rule_graph(&parse_input(INPUT).unwrap(), GraphFormat::Dot, Some(3)),
+      Err(Day5Error::UnknownUpdate { update: 3, updates: 2 })
+    );
   }
 }
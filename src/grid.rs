@@ -0,0 +1,308 @@
+use crate::parse_error::{ParseError, ParseErrorKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point {
+  pub x: usize,
+  pub y: usize,
+}
+
+impl Point {
+  pub fn new(x: usize, y: usize) -> Point {
+    Point { x, y }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+  North,
+  NorthEast,
+  East,
+  SouthEast,
+  South,
+  SouthWest,
+  West,
+  NorthWest,
+}
+
+impl Direction {
+  pub const ORTHOGONAL: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
+
+  pub const ALL: [Direction; 8] = [
+    Direction::North,
+    Direction::NorthEast,
+    Direction::East,
+    Direction::SouthEast,
+    Direction::South,
+    Direction::SouthWest,
+    Direction::West,
+    Direction::NorthWest,
+  ];
+
+  // y grows downwards, so North is -1
+  pub fn offset(&self) -> (isize, isize) {
+    match self {
+      Direction::North => (0, -1),
+      Direction::NorthEast => (1, -1),
+      Direction::East => (1, 0),
+      Direction::SouthEast => (1, 1),
+      Direction::South => (0, 1),
+      Direction::SouthWest => (-1, 1),
+      Direction::West => (-1, 0),
+      Direction::NorthWest => (-1, -1),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+  width: usize,
+  height: usize,
+  cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+  pub fn from_rows(rows: Vec<Vec<T>>) -> Grid<T> {
+    let height = rows.len();
+    let width = rows.first().map_or(0, |row| row.len());
+    let mut cells = Vec::with_capacity(width * height);
+    for row in rows {
+      assert_eq!(row.len(), width, "every row of a grid must have the same length");
+      cells.extend(row);
+    }
+
+    Grid { width, height, cells }
+  }
+
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
+  pub fn contains(&self, point: Point) -> bool {
+    point.x < self.width && point.y < self.height
+  }
+
+  pub fn get(&self, point: Point) -> Option<&T> {
+    if !self.contains(point) {
+      return None;
+    }
+
+    self.cells.get(point.y * self.width + point.x)
+  }
+
+  // The point one step away in `direction`, if it is still inside the grid
+  pub fn step(&self, point: Point, direction: Direction) -> Option<Point> {
+    let (dx, dy) = direction.offset();
+    let next = Point::new(point.x.checked_add_signed(dx)?, point.y.checked_add_signed(dy)?);
+
+    if self.contains(next) {
+      Some(next)
+    } else {
+      None
+    }
+  }
+
+  pub fn positions(&self) -> impl Iterator<Item = Point> + '_ {
+    (0..self.height).flat_map(move |y| (0..self.width).map(move |x| Point::new(x, y)))
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> + '_ {
+    self.positions().zip(self.cells.iter())
+  }
+
+  pub fn neighbours<'a>(&'a self, point: Point, directions: &'a [Direction]) -> impl Iterator<Item = (Point, &'a T)> + 'a {
+    directions
+      .iter()
+      .filter_map(move |direction| self.step(point, *direction))
+      .map(move |neighbour| (neighbour, &self.cells[neighbour.y * self.width + neighbour.x]))
+  }
+
+  pub fn neighbours_4(&self, point: Point) -> impl Iterator<Item = (Point, &T)> + '_ {
+    self.neighbours(point, &Direction::ORTHOGONAL)
+  }
+
+  pub fn neighbours_8(&self, point: Point) -> impl Iterator<Item = (Point, &T)> + '_ {
+    self.neighbours(point, &Direction::ALL)
+  }
+
+  // Every cell from `start` (inclusive) to the edge of the grid in `direction`
+  pub fn ray(&self, start: Point, direction: Direction) -> Ray<'_, T> {
+    Ray {
+      grid: self,
+      next: Some(start).filter(|point| self.contains(*point)),
+      direction,
+    }
+  }
+
+  pub fn row(&self, y: usize) -> Option<&[T]> {
+    if y >= self.height {
+      return None;
+    }
+
+    Some(&self.cells[y * self.width..(y + 1) * self.width])
+  }
+
+  pub fn rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+    (0..self.height).map(move |y| &self.cells[y * self.width..(y + 1) * self.width])
+  }
+
+  pub fn column(&self, x: usize) -> Ray<'_, T> {
+    self.ray(Point::new(x, 0), Direction::South)
+  }
+
+  pub fn columns(&self) -> impl Iterator<Item = Ray<'_, T>> + '_ {
+    (0..self.width).map(move |x| self.column(x))
+  }
+
+  // Top-left to bottom-right diagonals, starting along the left column then the top row
+  pub fn diagonals(&self) -> impl Iterator<Item = Ray<'_, T>> + '_ {
+    let left_column = (0..self.height).rev().map(|y| Point::new(0, y));
+    let top_row = (1..self.width).map(|x| Point::new(x, 0));
+
+    left_column.chain(top_row).map(move |start| self.ray(start, Direction::SouthEast))
+  }
+
+  // Top-right to bottom-left diagonals, starting along the top row then the right column
+  pub fn anti_diagonals(&self) -> impl Iterator<Item = Ray<'_, T>> + '_ {
+    let top_row = (0..self.width).map(|x| Point::new(x, 0));
+    let right_column = (1..self.height).map(move |y| Point::new(self.width.saturating_sub(1), y));
+
+    top_row.chain(right_column).map(move |start| self.ray(start, Direction::SouthWest))
+  }
+}
+
+impl<T: Clone> Grid<T> {
+  pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+    Grid {
+      width: self.width,
+      height: self.height,
+      cells: self.cells.iter().map(f).collect(),
+    }
+  }
+
+  pub fn rotate_clockwise(&self) -> Grid<T> {
+    let rows = (0..self.width)
+      .map(|x| (0..self.height).rev().map(|y| self.cells[y * self.width + x].clone()).collect())
+      .collect();
+
+    Grid::from_rows(rows)
+  }
+
+  // Mirror left to right
+  pub fn flip_horizontal(&self) -> Grid<T> {
+    let rows = self.rows().map(|row| row.iter().rev().cloned().collect()).collect();
+
+    Grid::from_rows(rows)
+  }
+}
+
+impl Grid<char> {
+  // For inputs known to be rectangular, such as literals in the source
+  pub fn parse(input: &str) -> Grid<char> {
+    Grid::from_rows(input.lines().map(|line| line.chars().collect()).collect())
+  }
+
+  // For puzzle inputs, where a short or long row is reported against its line instead of panicking
+  pub fn try_parse(day: u8, input: &str) -> Result<Grid<char>, ParseError> {
+    let mut rows: Vec<Vec<char>> = Vec::new();
+    for (line_index, line) in input.lines().enumerate() {
+      let row: Vec<char> = line.chars().collect();
+      if let Some(first_row) = rows.first() {
+        if row.len() != first_row.len() {
+          let kind = ParseErrorKind::RaggedRow { expected: first_row.len(), found: row.len() };
+          return Err(ParseError::new(day, line_index + 1, first_row.len().min(row.len()) + 1, line, kind));
+        }
+      }
+      rows.push(row);
+    }
+
+    Ok(Grid::from_rows(rows))
+  }
+}
+
+pub struct Ray<'a, T> {
+  grid: &'a Grid<T>,
+  next: Option<Point>,
+  direction: Direction,
+}
+
+impl<'a, T> Iterator for Ray<'a, T> {
+  type Item = (Point, &'a T);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let point = self.next?;
+    self.next = self.grid.step(point, self.direction);
+
+    Some((point, &self.grid.cells[point.y * self.grid.width + point.x]))
+  }
+}
+
+#[cfg(test)]
+mod grid_tests {
+  use super::*;
+
+  fn sample() -> Grid<char> {
+    Grid::parse("ABC\nDEF")
+  }
+
+  #[test]
+  fn get_is_checked_at_the_edges() {
+    let grid = sample();
+
+    assert_eq!(grid.width(), 3);
+    assert_eq!(grid.height(), 2);
+    assert_eq!(grid.get(Point::new(2, 1)), Some(&'F'));
+    assert_eq!(grid.get(Point::new(3, 0)), None);
+    assert_eq!(grid.get(Point::new(0, 2)), None);
+  }
+
+  #[test]
+  fn neighbours_stay_inside_the_grid() {
+    let grid = sample();
+
+    let orthogonal = grid.neighbours_4(Point::new(0, 0)).map(|(_, c)| *c).collect::<String>();
+    let all = grid.neighbours_8(Point::new(1, 1)).map(|(_, c)| *c).collect::<String>();
+
+    assert_eq!(orthogonal, "BD");
+    assert_eq!(all, "BCFDA");
+  }
+
+  #[test]
+  fn ray_runs_to_the_edge() {
+    let grid = sample();
+
+    assert_eq!(grid.ray(Point::new(0, 0), Direction::East).map(|(_, c)| *c).collect::<String>(), "ABC");
+    assert_eq!(grid.ray(Point::new(2, 1), Direction::NorthWest).map(|(_, c)| *c).collect::<String>(), "FB");
+    assert_eq!(grid.ray(Point::new(5, 5), Direction::East).count(), 0);
+  }
+
+  #[test]
+  fn views_cover_rows_columns_and_diagonals() {
+    let grid = sample();
+    let to_string = |ray: Ray<'_, char>| ray.map(|(_, c)| *c).collect::<String>();
+
+    assert_eq!(grid.rows().map(|row| row.iter().collect::<String>()).collect::<Vec<_>>(), vec!["ABC", "DEF"]);
+    assert_eq!(grid.columns().map(to_string).collect::<Vec<_>>(), vec!["AD", "BE", "CF"]);
+    assert_eq!(grid.diagonals().map(to_string).collect::<Vec<_>>(), vec!["D", "AE", "BF", "C"]);
+    assert_eq!(grid.anti_diagonals().map(to_string).collect::<Vec<_>>(), vec!["A", "BD", "CE", "F"]);
+  }
+
+  #[test]
+  fn try_parse_reports_ragged_rows() {
+    let error = Grid::try_parse(4, "ABC\nDEF\nGH\nIJK").unwrap_err();
+
+    assert_eq!(error, ParseError::new(4, 3, 3, "GH", ParseErrorKind::RaggedRow { expected: 3, found: 2 }));
+    assert_eq!(error.to_string(), "day 4, line 3, column 3: row has 2 cells but the first row has 3: `GH`");
+  }
+
+  #[test]
+  fn rotate_and_flip() {
+    let grid = sample();
+
+    assert_eq!(grid.rotate_clockwise(), Grid::parse("DA\nEB\nFC"));
+    assert_eq!(grid.flip_horizontal(), Grid::parse("CBA\nFED"));
+  }
+}
@@ -0,0 +1,218 @@
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+const DEFAULT_ROOT: &str = "input";
+const ROOT_VARIABLE: &str = "AOC_INPUT_DIR";
+
+// Inputs live at `<root>/<year>/day<N>.txt`, with extra named ones next to them as `day<N>.<name>.txt`,
+// e.g. `input/2024/day5.example.txt`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputStore {
+  root: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Input {
+  pub year: u16,
+  pub day: u8,
+  pub name: Option<String>,
+  pub path: PathBuf,
+  pub text: String,
+  pub checksum: Checksum,
+}
+
+// FNV-1a of the normalised text, so a file re-saved with CRLF endings keeps its checksum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Checksum(pub u64);
+
+impl fmt::Display for Checksum {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:016x}", self.0)
+  }
+}
+
+#[derive(Debug)]
+pub enum InputError {
+  InvalidName(String),
+  Read { path: PathBuf, error: io::Error },
+}
+
+impl fmt::Display for InputError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      InputError::InvalidName(name) => {
+        write!(f, "invalid input name `{}`, only letters, digits, `-` and `_` are allowed", name)
+      }
+      InputError::Read { path, error } => write!(f, "could not read {}: {}", path.display(), error),
+    }
+  }
+}
+
+impl Error for InputError {}
+
+impl InputStore {
+  pub fn new(root: impl Into<PathBuf>) -> InputStore {
+    InputStore { root: root.into() }
+  }
+
+  // $AOC_INPUT_DIR, falling back to `input` in the working directory
+  pub fn from_env() -> InputStore {
+    InputStore::new(env::var_os(ROOT_VARIABLE).map(PathBuf::from).unwrap_or_else(|| PathBuf::from(DEFAULT_ROOT)))
+  }
+
+  pub fn path(&self, year: u16, day: u8, name: Option<&str>) -> Result<PathBuf, InputError> {
+    let file_name = match name {
+      None => format!("day{}.txt", day),
+      Some(name) if is_valid_name(name) => format!("day{}.{}.txt", day, name),
+      Some(name) => return Err(InputError::InvalidName(name.to_string())),
+    };
+
+    Ok(self.root.join(year.to_string()).join(file_name))
+  }
+
+  // Submitted answers for every input of the year, see answers::Guess
+  pub fn answers_path(&self, year: u16) -> PathBuf {
+    self.root.join(year.to_string()).join("answers.txt")
+  }
+
+  pub fn load(&self, year: u16, day: u8, name: Option<&str>) -> Result<Input, InputError> {
+    let path = self.path(year, day, name)?;
+    let raw = fs::read_to_string(&path).map_err(|error| InputError::Read { path: path.clone(), error })?;
+    let text = normalise(&raw);
+
+    Ok(Input {
+      year,
+      day,
+      name: name.map(str::to_string),
+      path,
+      checksum: checksum(&text),
+      text,
+    })
+  }
+
+  // The inputs on disk for a day: `None` for the plain `day<N>.txt` first, then named ones alphabetically
+  pub fn available(&self, year: u16, day: u8) -> Result<Vec<Option<String>>, InputError> {
+    let directory = self.root.join(year.to_string());
+    let entries = match fs::read_dir(&directory) {
+      Ok(entries) => entries,
+      Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+      Err(error) => return Err(InputError::Read { path: directory, error }),
+    };
+
+    let prefix = format!("day{}.", day);
+    let mut names = Vec::new();
+    for entry in entries {
+      let entry = entry.map_err(|error| InputError::Read { path: directory.clone(), error })?;
+      let file_name = entry.file_name().to_string_lossy().into_owned();
+      match file_name.strip_prefix(&prefix) {
+        Some("txt") => names.push(None),
+        Some(rest) => match rest.strip_suffix(".txt") {
+          Some(name) if is_valid_name(name) => names.push(Some(name.to_string())),
+          _ => {}
+        },
+        None => {}
+      }
+    }
+    names.sort();
+
+    Ok(names)
+  }
+}
+
+// CRLF and lone CR become LF, and trailing newlines are dropped, so every input looks like it was saved on unix
+// by an editor that doesn't add a final newline
+pub fn normalise(raw: &str) -> String {
+  let unix = raw.replace("\r\n", "\n").replace('\r', "\n");
+
+  unix.trim_end_matches('\n').to_string()
+}
+
+pub fn checksum(text: &str) -> Checksum {
+  let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+
+  Checksum(hash)
+}
+
+fn is_valid_name(name: &str) -> bool {
+  !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod inputs_tests {
+  use super::*;
+
+  // Deletes the store's directory when dropped, so test runs don't leave inputs behind in the temp directory
+  struct TempStore(InputStore);
+
+  impl Drop for TempStore {
+    fn drop(&mut self) {
+      let _ = fs::remove_dir_all(&self.0.root);
+    }
+  }
+
+  // `test` names the directory, so tests running in parallel each get their own
+  fn store_with(test: &str, files: &[(&str, &str)]) -> TempStore {
+    let root = env::temp_dir().join(format!("aoc-inputs-{}-{}", std::process::id(), test));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("2024")).unwrap();
+    for (file_name, contents) in files {
+      fs::write(root.join("2024").join(file_name), contents).unwrap();
+    }
+
+    TempStore(InputStore::new(root))
+  }
+
+  #[test]
+  fn normalises_line_endings_and_trailing_newlines() {
+    assert_eq!(normalise("1 2\r\n3 4\r\n\r\n"), "1 2\n3 4");
+    assert_eq!(normalise("1 2\r3 4\n"), "1 2\n3 4");
+    assert_eq!(normalise("  mul(2,3) "), "  mul(2,3) ");
+  }
+
+  #[test]
+  fn checksum_ignores_line_ending_style() {
+    assert_eq!(checksum(&normalise("a\r\nb\r\n")), checksum(&normalise("a\nb")));
+    assert_ne!(checksum("a\nb"), checksum("a\nc"));
+    assert_eq!(checksum("").to_string(), "cbf29ce484222325");
+  }
+
+  #[test]
+  fn resolves_plain_and_named_inputs() {
+    let store = InputStore::new("inputs");
+
+    assert_eq!(store.path(2024, 5, None).unwrap(), PathBuf::from("inputs/2024/day5.txt"));
+    assert_eq!(store.path(2024, 5, Some("example")).unwrap(), PathBuf::from("inputs/2024/day5.example.txt"));
+    assert!(matches!(store.path(2024, 5, Some("../day4")), Err(InputError::InvalidName(_))));
+    assert_eq!(store.answers_path(2024), PathBuf::from("inputs/2024/answers.txt"));
+  }
+
+  #[test]
+  fn loads_and_lists_inputs() {
+    let temp_store = store_with(
+      "loads_and_lists_inputs",
+      &[("day5.txt", "47|53\r\n\r\n75,47,61\r\n"), ("day5.example.txt", "1|2"), ("day51.txt", ""), ("day5.alice.txt", "")],
+    );
+    let store = &temp_store.0;
+
+    let input = store.load(2024, 5, None).unwrap();
+
+    assert_eq!(input.text, "47|53\n\n75,47,61");
+    assert_eq!(input.checksum, checksum("47|53\n\n75,47,61"));
+    assert_eq!(
+      store.available(2024, 5).unwrap(),
+      vec![None, Some(String::from("alice")), Some(String::from("example"))]
+    );
+    assert_eq!(store.available(2023, 5).unwrap(), Vec::<Option<String>>::new());
+  }
+
+  #[test]
+  fn missing_inputs_name_the_path() {
+    let error = InputStore::new("nowhere").load(2024, 1, Some("example")).unwrap_err();
+
+    assert!(error.to_string().starts_with("could not read nowhere/2024/day1.example.txt: "));
+  }
+}
@@ -3,10 +3,22 @@ extern crate aoc_runner;
 #[macro_use]
 extern crate aoc_runner_derive;
 
+pub mod answers;
+pub mod bench;
+pub mod cross_check;
 pub mod day1;
 pub mod day2;
 pub mod day3;
 pub mod day4;
 pub mod day5;
+pub mod grid;
+pub mod inputs;
+pub mod parse_error;
+pub mod pattern;
+pub mod render;
+pub mod solve_error;
+pub mod solvers;
+pub mod table;
+pub mod word_search;
 
 aoc_lib!{ year = 2024 }
\ No newline at end of file
@@ -1,7 +1,536 @@
 extern crate advent_of_code_2024;
-extern crate aoc_runner_derive;
-extern crate aoc_runner;
 
-use aoc_runner_derive::aoc_main;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::process;
+use std::time::Instant;
 
-aoc_main! { lib = advent_of_code_2024 }
\ No newline at end of file
+use advent_of_code_2024::answers::{verification_table, Answers, Check, Guess, Response, Verification};
+use advent_of_code_2024::bench::{self, comparison_table, compare, CountingAllocator};
+use advent_of_code_2024::cross_check::{cross_check_table, CrossCheck};
+use advent_of_code_2024::day2::{analysis_json, analysis_table, analyze, count_safe_reports, parse_reports, tolerance_curve, SafetyPolicy};
+use advent_of_code_2024::day3::{diagnose, evaluate_reader, Instruction, InstructionSet};
+use advent_of_code_2024::day5::{export_rule_graph, parse_input, GraphFormat};
+use advent_of_code_2024::inputs::{normalise, InputStore};
+use advent_of_code_2024::parse_error::ParseErrorKind;
+use advent_of_code_2024::solvers::{select, variants, ParsedInputs, Solver, SOLVERS};
+
+const USAGE: &str = "usage: advent-of-code-2024
+  [run [--day N] [--part N] [--name NAME | --input PATH|-]]
+  [list]
+  [inputs [--day N]]
+  [bench [--day N] [--part N] [--name NAME] [--iterations N] [--json PATH] [--baseline PATH] [--threshold PERCENT]]
+  [cross-check [--day N] [--part N] [--name NAME | --input PATH|-] [--iterations N]]
+  [check]
+  [verify [--day N] [--part N]]
+  [record --day N --part N [--name NAME] --correct|--too-low|--too-high|--wrong ANSWER]
+  [day2-analysis table|json [POLICY] [--name NAME | --input PATH|-]]
+  [day2-safety [POLICY] [--name NAME | --input PATH|-]]
+  [day3-diagnose [--rejected-only] [--name NAME | --input PATH|-]]
+  [day3-stream [--part 1|2] [--name NAME | --input PATH|-]]
+  [day5-graph dot|mermaid [--update N] [--name NAME | --input PATH|-]]
+POLICY is [--policy FILE] [--min-step N] [--max-step N] [--direction increasing|decreasing|either|non-strict|any] [--max-plateau N]
+inputs are read from $AOC_INPUT_DIR (default `input`) as YEAR/dayN.txt, or YEAR/dayN.NAME.txt with --name";
+
+const YEAR: u16 = 2024;
+const BENCH_ITERATIONS: u32 = 100;
+const BENCH_THRESHOLD_PERCENT: f64 = 10.0;
+const POLICY_FLAGS: [&str; 7] = ["--policy", "--min-step", "--max-step", "--direction", "--max-plateau", "--name", "--input"];
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+
+  let command = args.first().map(String::as_str).unwrap_or("run");
+  let rest = args.get(1..).unwrap_or(&[]);
+  let result = match command {
+    "run" => run(rest),
+    "list" => list(rest),
+    "inputs" => inputs(rest),
+    "bench" => bench(rest),
+    "cross-check" => cross_check(rest),
+    "check" => check(rest),
+    "verify" => verify(rest),
+    "record" => record(rest),
+    "day2-analysis" => run_day2_analysis(rest),
+    "day2-safety" => run_day2_safety(rest),
+    "day3-diagnose" => run_day3_diagnose(rest),
+    "day3-stream" => run_day3_stream(rest),
+    "day5-graph" => run_day5_graph(rest),
+    other => Err(format!("unknown command `{}`", other)),
+  };
+  if let Err(message) = result {
+    eprintln!("{}\n{}", message, USAGE);
+    process::exit(2);
+  }
+}
+
+struct Options {
+  day: Option<u8>,
+  part: Option<u8>,
+  name: Option<String>,
+  input: Option<String>,
+  iterations: u32,
+  response: Option<(Response, String)>,
+  json: Option<String>,
+  baseline: Option<String>,
+  threshold_percent: f64,
+  update: Option<usize>,
+  rejected_only: bool,
+  policy: Option<String>,
+  policy_overrides: Vec<(String, String)>,
+}
+
+// `allowed` lists the flags a subcommand takes, every flag but --rejected-only has a value
+fn parse_options(args: &[String], allowed: &[&str]) -> Result<Options, String> {
+  let mut options = Options {
+    day: None,
+    part: None,
+    name: None,
+    input: None,
+    iterations: BENCH_ITERATIONS,
+    response: None,
+    json: None,
+    baseline: None,
+    threshold_percent: BENCH_THRESHOLD_PERCENT,
+    update: None,
+    rejected_only: false,
+    policy: None,
+    policy_overrides: Vec::new(),
+  };
+
+  let mut args = args.iter();
+  while let Some(arg) = args.next() {
+    if !allowed.contains(&arg.as_str()) {
+      return Err(format!("unknown argument `{}`", arg));
+    }
+    if arg == "--rejected-only" {
+      options.rejected_only = true;
+      continue;
+    }
+    let value = args.next().ok_or(format!("missing value for {}", arg))?;
+    let invalid = || format!("invalid value `{}` for {}", value, arg);
+    match arg.as_str() {
+      "--day" => options.day = Some(value.parse().map_err(|_| invalid())?),
+      "--part" => options.part = Some(value.parse().map_err(|_| invalid())?),
+      "--iterations" => options.iterations = value.parse().map_err(|_| invalid())?,
+      "--name" => options.name = Some(value.clone()),
+      "--json" => options.json = Some(value.clone()),
+      "--baseline" => options.baseline = Some(value.clone()),
+      "--threshold" => options.threshold_percent = value.parse().map_err(|_| invalid())?,
+      "--update" => options.update = Some(value.parse().map_err(|_| invalid())?),
+      "--policy" => options.policy = Some(value.clone()),
+      "--min-step" | "--max-step" | "--direction" | "--max-plateau" => options.policy_overrides.push((arg.clone(), value.clone())),
+      "--correct" | "--too-low" | "--too-high" | "--wrong" => {
+        options.response = Some((arg[2..].parse().map_err(|_| invalid())?, value.clone()))
+      }
+      _ => options.input = Some(value.clone()),
+    }
+  }
+
+  Ok(options)
+}
+
+fn read_input(options: &Options, day: u8) -> Result<String, String> {
+  read_day_input(day, options.input.as_deref(), options.name.as_deref())
+}
+
+// --input bypasses the input store, `-` reads stdin. Either way the text is normalised like a stored input.
+fn read_day_input(day: u8, input: Option<&str>, name: Option<&str>) -> Result<String, String> {
+  match input {
+    Some("-") => {
+      let mut input = String::new();
+      io::stdin().read_to_string(&mut input).map_err(|e| format!("could not read stdin: {}", e))?;
+      Ok(normalise(&input))
+    }
+    Some(path) => fs::read_to_string(path).map(|input| normalise(&input)).map_err(|e| format!("could not read {}: {}", path, e)),
+    None => {
+      let input = InputStore::from_env().load(YEAR, day, name).map_err(|e| e.to_string())?;
+      Ok(input.text)
+    }
+  }
+}
+
+// Each day's input is read once, even when several solvers share it
+fn inputs_for(options: &Options, solvers: &[&Solver]) -> Result<Vec<(u8, String)>, String> {
+  if options.input.is_some() && options.name.is_some() {
+    return Err(String::from("--input and --name can't be used together"));
+  }
+  if options.input.is_some() && solvers.iter().any(|solver| solver.day != solvers[0].day) {
+    return Err(String::from("--input needs --day"));
+  }
+
+  let mut inputs: Vec<(u8, String)> = Vec::new();
+  for solver in solvers {
+    if !inputs.iter().any(|(day, _)| *day == solver.day) {
+      inputs.push((solver.day, read_input(options, solver.day)?));
+    }
+  }
+
+  Ok(inputs)
+}
+
+fn input_of(inputs: &[(u8, String)], day: u8) -> &str {
+  inputs.iter().find(|(input_day, _)| *input_day == day).map_or("", |(_, input)| input.as_str())
+}
+
+fn solvers_with_inputs(options: &Options) -> Result<Vec<(&'static Solver, String)>, String> {
+  let solvers = select(options.day, options.part).collect::<Vec<_>>();
+  if solvers.is_empty() {
+    return Err(String::from("no solver matches that day and part"));
+  }
+  let inputs = inputs_for(options, &solvers)?;
+
+  Ok(solvers.into_iter().map(|solver| (solver, input_of(&inputs, solver.day).to_string())).collect())
+}
+
+fn label(solver: &Solver) -> String {
+  format!("Day {} - Part {} - {}", solver.day, solver.part, solver.name)
+}
+
+// cargo run --release -- run --day 5 --part 2
+// cat input/2024/day3.txt | cargo run -- run --day 3 --input -
+// Parts that share a generator reuse the input it parsed for the first of them
+fn run(args: &[String]) -> Result<(), String> {
+  let options = parse_options(args, &["--day", "--part", "--name", "--input"])?;
+
+  let mut parsed_inputs = ParsedInputs::default();
+  for (solver, input) in solvers_with_inputs(&options)? {
+    let start = Instant::now();
+    let (parsed, fresh) = parsed_inputs.get_or_parse(solver, &input).map_err(|e| format!("{}: {}", label(solver), e))?;
+    let generated = Instant::now();
+    let answer = (solver.runner)(&input, &*parsed).map_err(|e| format!("{}: {}", label(solver), e))?;
+    let generator = match (&solver.generator, fresh) {
+      (None, _) => String::from("none"),
+      (Some(_), true) => format!("{:?}", generated - start),
+      (Some(_), false) => String::from("reused"),
+    };
+    println!("{}: {}\n\tgenerator: {},\n\trunner: {:?}\n", label(solver), answer, generator, generated.elapsed());
+  }
+
+  Ok(())
+}
+
+fn list(args: &[String]) -> Result<(), String> {
+  parse_options(args, &[])?;
+
+  for solver in SOLVERS {
+    println!("{}", label(solver));
+  }
+
+  Ok(())
+}
+
+// Every input on disk for the days that have solvers, with the checksum of its normalised text
+fn inputs(args: &[String]) -> Result<(), String> {
+  let options = parse_options(args, &["--day"])?;
+  let store = InputStore::from_env();
+
+  let mut days = select(options.day, None).map(|solver| solver.day).collect::<Vec<_>>();
+  days.dedup();
+  for day in days {
+    for name in store.available(YEAR, day).map_err(|e| e.to_string())? {
+      let input = store.load(YEAR, day, name.as_deref()).map_err(|e| e.to_string())?;
+      println!("{}  {}", input.checksum, input.path.display());
+    }
+  }
+
+  Ok(())
+}
+
+// cargo run --release -- bench --iterations 1000 --json bench.json
+// cargo run --release -- bench --baseline bench.json --threshold 20
+// Exits with 1 if any median is more than --threshold percent slower than in the baseline
+fn bench(args: &[String]) -> Result<(), String> {
+  let options = parse_options(
+    args,
+    &["--day", "--part", "--name", "--iterations", "--json", "--baseline", "--threshold"],
+  )?;
+  let baseline = match &options.baseline {
+    Some(path) => {
+      let json = fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path, e))?;
+      bench::from_json(&json).map_err(|e| format!("{}: {}", path, e))?
+    }
+    None => Vec::new(),
+  };
+
+  let mut benchmarks = Vec::new();
+  for (solver, input) in solvers_with_inputs(&options)? {
+    let benchmark = bench::run(solver, options.name.as_deref(), &input, options.iterations)
+      .map_err(|e| format!("{}: {}", label(solver), e))?;
+    benchmarks.push(benchmark);
+  }
+
+  let comparisons = compare(&benchmarks, &baseline, options.threshold_percent / 100.0);
+  print!("{}", comparison_table(&comparisons));
+  if let Some(path) = &options.json {
+    fs::write(path, bench::to_json(&benchmarks)).map_err(|e| format!("could not write {}: {}", path, e))?;
+  }
+
+  let regressions = comparisons.iter().filter(|comparison| comparison.regression).count();
+  if regressions > 0 {
+    eprintln!("{} of {} benchmarks are more than {}% slower than the baseline", regressions, comparisons.len(), options.threshold_percent);
+    process::exit(1);
+  }
+
+  Ok(())
+}
+
+// cargo run --release -- cross-check --day 5 --iterations 20
+// Runs every variant of each part that has more than one on the same input, and exits with 1 if any of them
+// fails or disagrees with the part's first variant
+fn cross_check(args: &[String]) -> Result<(), String> {
+  let options = parse_options(args, &["--day", "--part", "--name", "--input", "--iterations"])?;
+  let groups = variants(options.day, options.part);
+  if groups.is_empty() {
+    return Err(String::from("no part matching that day and part has more than one variant"));
+  }
+  let inputs = inputs_for(&options, &groups.iter().map(|group| group[0]).collect::<Vec<_>>())?;
+
+  let checks = groups
+    .iter()
+    .map(|group| CrossCheck::run(group, options.name.as_deref(), input_of(&inputs, group[0].day), options.iterations))
+    .collect::<Vec<_>>();
+
+  print!("{}", cross_check_table(&checks));
+  let disagreements = checks.iter().filter(|check| !check.agrees()).count();
+  if disagreements > 0 {
+    eprintln!("{} of {} parts have variants that disagree", disagreements, checks.len());
+    process::exit(1);
+  }
+
+  Ok(())
+}
+
+// Runs every solver on its input and exits with 1 if any of them fails
+fn check(args: &[String]) -> Result<(), String> {
+  let options = parse_options(args, &[])?;
+
+  let mut failures = 0;
+  for solver in SOLVERS {
+    let result = read_input(&options, solver.day).and_then(|input| solver.solve(&input).map_err(|e| e.to_string()));
+    match result {
+      Ok(_) => println!("{}: ok", label(solver)),
+      Err(message) => {
+        println!("{}: FAILED {}", label(solver), message);
+        failures += 1;
+      }
+    }
+  }
+
+  if failures > 0 {
+    eprintln!("{} of {} solvers failed", failures, SOLVERS.len());
+    process::exit(1);
+  }
+
+  Ok(())
+}
+
+fn load_answers(store: &InputStore) -> Result<Answers, String> {
+  let path = store.answers_path(YEAR);
+  match fs::read_to_string(&path) {
+    Ok(text) => Answers::parse(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+    Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Answers::default()),
+    Err(e) => Err(format!("could not read {}: {}", path.display(), e)),
+  }
+}
+
+// Runs every verifiable solver on every input of its day and compares the answers with the answers file.
+// Exits with 1 if any answer doesn't match, was already rejected, or was recorded for a different input.
+fn verify(args: &[String]) -> Result<(), String> {
+  let options = parse_options(args, &["--day", "--part"])?;
+  let store = InputStore::from_env();
+  let answers = load_answers(&store)?;
+
+  let mut verifications = Vec::new();
+  for solver in select(options.day, options.part).filter(|solver| solver.verifiable) {
+    for name in store.available(YEAR, solver.day).map_err(|e| e.to_string())? {
+      let verification = match store.load(YEAR, solver.day, name.as_deref()) {
+        Ok(input) => {
+          let actual = solver.solve(&input.text).map_err(|e| e.to_string());
+          let check = match &actual {
+            Ok(answer) => answers.check(solver.day, solver.part, name.as_deref(), answer, input.checksum),
+            Err(_) => Check::Unrecorded,
+          };
+          Verification { day: solver.day, part: solver.part, solver: solver.name, input: name, actual, check }
+        }
+        Err(e) => Verification {
+          day: solver.day,
+          part: solver.part,
+          solver: solver.name,
+          input: name,
+          actual: Err(e.to_string()),
+          check: Check::Unrecorded,
+        },
+      };
+      verifications.push(verification);
+    }
+  }
+
+  print!("{}", verification_table(&verifications));
+  let failures = verifications.iter().filter(|verification| verification.is_failure()).count();
+  if failures > 0 {
+    eprintln!("{} of {} answers failed verification", failures, verifications.len());
+    process::exit(1);
+  }
+
+  Ok(())
+}
+
+// cargo run -- record --day 3 --part 2 --too-low 69779216
+// Correct answers are recorded with the checksum of the input they were computed from.
+// Only parts with a verifiable solver take answers, since verify has nothing else to check them with.
+fn record(args: &[String]) -> Result<(), String> {
+  let options = parse_options(args, &["--day", "--part", "--name", "--correct", "--too-low", "--too-high", "--wrong"])?;
+  let (Some(day), Some(part), Some((response, answer))) = (options.day, options.part, options.response.clone()) else {
+    return Err(String::from("record needs --day, --part and a response"));
+  };
+  if !select(Some(day), Some(part)).any(|solver| solver.verifiable) {
+    return Err(format!("day {} part {} has no verifiable solver to record answers for", day, part));
+  }
+  let store = InputStore::from_env();
+  let answers = load_answers(&store)?;
+
+  let input_checksum = match response {
+    Response::Correct => Some(store.load(YEAR, day, options.name.as_deref()).map_err(|e| e.to_string())?.checksum),
+    _ => None,
+  };
+  if let Some(recorded) = answers
+    .for_input(day, part, options.name.as_deref())
+    .find(|guess| guess.response == Response::Correct && guess.answer != answer)
+  {
+    return Err(format!("a different correct answer is already recorded: {}", recorded));
+  }
+
+  let guess = Guess::new(day, part, options.name.clone(), response, answer, input_checksum)?;
+  let path = store.answers_path(YEAR);
+  let mut text = fs::read_to_string(&path).unwrap_or_default();
+  if !text.is_empty() && !text.ends_with('\n') {
+    text.push('\n');
+  }
+  text.push_str(&format!("{}\n", guess));
+  fs::write(&path, text).map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+  println!("{}", guess);
+
+  Ok(())
+}
+
+// cargo run -- day2-analysis json --max-plateau 1 > day2.json
+fn run_day2_analysis(args: &[String]) -> Result<(), String> {
+  let (format, rest) = args.split_first().ok_or("missing format for day2-analysis")?;
+  let options = parse_options(rest, &POLICY_FLAGS)?;
+  let policy = safety_policy(&options)?;
+
+  let reports = parse_reports(&read_input(&options, 2)?).map_err(|e| e.to_string())?;
+  let analysis = analyze(&reports, &policy);
+  match format.as_str() {
+    "table" => print!("{}", analysis_table(&analysis)),
+    "json" => print!("{}", analysis_json(&analysis)),
+    other => return Err(format!("unknown analysis format `{}`", other)),
+  }
+
+  Ok(())
+}
+
+// cargo run -- day2-safety --policy reactor.toml --max-step 4
+fn run_day2_safety(args: &[String]) -> Result<(), String> {
+  let options = parse_options(args, &POLICY_FLAGS)?;
+  let policy = safety_policy(&options)?;
+
+  let reports = parse_reports(&read_input(&options, 2)?).map_err(|e| e.to_string())?;
+  let safe_reports = count_safe_reports(&reports, &policy);
+  let dampened_reports = tolerance_curve(&reports, 1, &policy)[1];
+  println!("{:?}\nsafe reports: {}\nsafe with the dampener: {}", policy, safe_reports, dampened_reports);
+
+  Ok(())
+}
+
+// Flags override the values from the policy file, which in turn override the puzzle's rules
+fn safety_policy(options: &Options) -> Result<SafetyPolicy, String> {
+  let policy = match &options.policy {
+    Some(path) => {
+      let toml = fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path, e))?;
+      SafetyPolicy::from_toml(&toml).map_err(|e| format!("{}: {}", path, e))?
+    }
+    None => SafetyPolicy::default(),
+  };
+  // Each flag becomes one `key = value` line, so an error's line number is the position of the flag
+  let flags_toml = options
+    .policy_overrides
+    .iter()
+    .map(|(flag, value)| format!("{} = {}", flag.trim_start_matches("--").replace('-', "_"), value))
+    .collect::<Vec<String>>()
+    .join("\n");
+
+  policy.merge_toml(&flags_toml).map_err(|e| {
+    let (flag, value) = &options.policy_overrides[e.line - 1];
+    match e.kind {
+      ParseErrorKind::StepRange { min_step, max_step } => {
+        format!("invalid value `{}` for {}: min_step {} is above max_step {}", value, flag, min_step, max_step)
+      }
+      _ => format!("invalid value `{}` for {}", value, flag),
+    }
+  })
+}
+
+// cargo run -- day3-diagnose --rejected-only | less
+fn run_day3_diagnose(args: &[String]) -> Result<(), String> {
+  let options = parse_options(args, &["--rejected-only", "--name", "--input"])?;
+
+  let input = read_input(&options, 3)?;
+  let instructions = InstructionSet::puzzle();
+  let diagnostics = diagnose(&input, &instructions);
+  for diagnostic in diagnostics.iter().filter(|d| !options.rejected_only || d.outcome.is_err()) {
+    println!("{}", diagnostic);
+  }
+  let accepted = diagnostics.iter().filter(|d| d.outcome.is_ok()).count();
+  println!("{} accepted, {} rejected", accepted, diagnostics.len() - accepted);
+
+  Ok(())
+}
+
+// zcat dump.gz | cargo run --release -- day3-stream --input -
+// Stored inputs are streamed from their file too, so they aren't normalised, which the lexer doesn't need
+fn run_day3_stream(args: &[String]) -> Result<(), String> {
+  let options = parse_options(args, &["--part", "--name", "--input"])?;
+  let instructions = match options.part {
+    Some(1) => InstructionSet::new().with(Instruction::mul()),
+    Some(2) | None => InstructionSet::puzzle(),
+    Some(part) => return Err(format!("day 3 has no part {}", part)),
+  };
+
+  let input_path = match options.input {
+    Some(path) => path,
+    None => InputStore::from_env().path(YEAR, 3, options.name.as_deref()).map_err(|e| e.to_string())?.display().to_string(),
+  };
+  let total = if input_path == "-" {
+    evaluate_reader(io::stdin().lock(), &instructions)
+  } else {
+    let file = File::open(&input_path).map_err(|e| format!("could not read {}: {}", input_path, e))?;
+    evaluate_reader(BufReader::new(file), &instructions)
+  }
+  .map_err(|e| format!("{}: {}", input_path, e))?;
+  println!("{}", total);
+
+  Ok(())
+}
+
+// cargo run -- day5-graph dot --update 4 | dot -Tsvg > update4.svg
+fn run_day5_graph(args: &[String]) -> Result<(), String> {
+  let (format, rest) = args.split_first().ok_or("missing format for day5-graph")?;
+  let format = match format.as_str() {
+    "dot" => GraphFormat::Dot,
+    "mermaid" => GraphFormat::Mermaid,
+    other => return Err(format!("unknown graph format `{}`", other)),
+  };
+  let options = parse_options(rest, &["--update", "--name", "--input"])?;
+
+  let rules_and_print_orders = parse_input(&read_input(&options, 5)?).map_err(|e| e.to_string())?;
+  let graph = export_rule_graph(&rules_and_print_orders, format, options.update).map_err(|e| e.to_string())?;
+  print!("{}", graph);
+
+  Ok(())
+}
@@ -0,0 +1,96 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+  InvalidNumber,
+  MissingSeparator(&'static str),
+  UnexpectedField,
+  RaggedRow { expected: usize, found: usize },
+  UnknownKey,
+  InvalidValue,
+  EvenLength(usize),
+  NoFixedCell,
+  StepRange { min_step: u32, max_step: u32 },
+}
+
+// Line and column are both 1-based so they match what an editor shows for input/2024/dayN.txt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+  pub day: u8,
+  pub line: usize,
+  pub column: usize,
+  pub text: String,
+  pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+  pub fn new(day: u8, line: usize, column: usize, text: &str, kind: ParseErrorKind) -> ParseError {
+    ParseError {
+      day,
+      line,
+      column,
+      text: text.to_string(),
+      kind,
+    }
+  }
+
+  // `token` must be a slice of `line`, which is always the case for the output of split and friends
+  pub fn at_token(day: u8, line_number: usize, line: &str, token: &str, kind: ParseErrorKind) -> ParseError {
+    ParseError::new(day, line_number, column_of(line, token), token, kind)
+  }
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "day {}, line {}, column {}: ", self.day, self.line, self.column)?;
+    match &self.kind {
+      ParseErrorKind::InvalidNumber => write!(f, "invalid number `{}`", self.text),
+      ParseErrorKind::MissingSeparator(separator) => write!(f, "expected `{}` in `{}`", separator, self.text),
+      ParseErrorKind::UnexpectedField => write!(f, "unexpected field `{}`", self.text),
+      ParseErrorKind::RaggedRow { expected, found } => {
+        write!(f, "row has {} cells but the first row has {}: `{}`", found, expected, self.text)
+      }
+      ParseErrorKind::UnknownKey => write!(f, "unknown key `{}`", self.text),
+      ParseErrorKind::InvalidValue => write!(f, "invalid value `{}`", self.text),
+      ParseErrorKind::StepRange { min_step, max_step } => write!(f, "min_step {} is above max_step {}", min_step, max_step),
+      ParseErrorKind::NoFixedCell => write!(f, "pattern has no cell other than `.`: `{}`", self.text),
+      ParseErrorKind::EvenLength(length) => write!(f, "expected an odd number of pages but found {}: `{}`", length, self.text),
+    }
+  }
+}
+
+impl Error for ParseError {}
+
+pub fn parse_number(day: u8, line_number: usize, line: &str, token: &str) -> Result<i32, ParseError> {
+  token
+    .parse::<i32>()
+    .map_err(|_| ParseError::at_token(day, line_number, line, token, ParseErrorKind::InvalidNumber))
+}
+
+fn column_of(line: &str, token: &str) -> usize {
+  let offset = (token.as_ptr() as usize).saturating_sub(line.as_ptr() as usize);
+  offset.min(line.len()) + 1
+}
+
+#[cfg(test)]
+mod parse_error_tests {
+  use super::*;
+
+  #[test]
+  fn parse_number_reports_the_column_of_the_token() {
+    let line = "12|4x";
+    let token = line.split('|').nth(1).unwrap();
+
+    let error = parse_number(5, 3, line, token).unwrap_err();
+
+    assert_eq!(error, ParseError::new(5, 3, 4, "4x", ParseErrorKind::InvalidNumber));
+  }
+
+  #[test]
+  fn display_includes_the_location() {
+    let error = ParseError::new(1, 7, 1, "3 4", ParseErrorKind::MissingSeparator("   "));
+
+    assert_eq!(error.to_string(), "day 1, line 7, column 1: expected `   ` in `3 4`");
+  }
+}
@@ -0,0 +1,128 @@
+use crate::grid::{Grid, Point};
+use crate::parse_error::{ParseError, ParseErrorKind};
+
+const WILDCARD: char = '.';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Placement {
+  // Top-left corner of the variant's bounding box
+  pub origin: Point,
+  pub variant: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternTemplate {
+  variants: Vec<Grid<Option<char>>>,
+}
+
+impl PatternTemplate {
+  // Every rotation and reflection of `mask`, where `.` matches any cell.
+  // Symmetric shapes produce duplicate variants, which are only kept once so nothing is counted twice.
+  // A mask of only wildcards would match every cell, so it needs at least one other character.
+  pub fn parse(day: u8, mask: &str) -> Result<PatternTemplate, ParseError> {
+    let base = Grid::try_parse(day, mask)?.map(|c| if *c == WILDCARD { None } else { Some(*c) });
+    if base.iter().all(|(_, cell)| cell.is_none()) {
+      return Err(ParseError::new(day, 1, 1, mask, ParseErrorKind::NoFixedCell));
+    }
+
+    let mut variants: Vec<Grid<Option<char>>> = Vec::new();
+    for mut variant in [base.clone(), base.flip_horizontal()] {
+      for _ in 0..4 {
+        if !variants.contains(&variant) {
+          variants.push(variant.clone());
+        }
+        variant = variant.rotate_clockwise();
+      }
+    }
+
+    Ok(PatternTemplate { variants })
+  }
+
+  pub fn variants(&self) -> &[Grid<Option<char>>] {
+    &self.variants
+  }
+
+  pub fn find(&self, grid: &Grid<char>) -> Vec<Placement> {
+    let mut placements = Vec::new();
+
+    for origin in grid.positions() {
+      for (index, variant) in self.variants.iter().enumerate() {
+        if matches_at(variant, grid, origin) {
+          placements.push(Placement { origin, variant: index });
+        }
+      }
+    }
+
+    placements
+  }
+
+  pub fn count(&self, grid: &Grid<char>) -> usize {
+    self.find(grid).len()
+  }
+
+  // The grid cells a placement actually constrains, wildcards excluded
+  pub fn positions(&self, placement: &Placement) -> impl Iterator<Item = Point> + '_ {
+    let origin = placement.origin;
+    self.variants[placement.variant]
+      .iter()
+      .filter(|(_, cell)| cell.is_some())
+      .map(move |(offset, _)| Point::new(origin.x + offset.x, origin.y + offset.y))
+  }
+}
+
+fn matches_at(variant: &Grid<Option<char>>, grid: &Grid<char>, origin: Point) -> bool {
+  if origin.x + variant.width() > grid.width() || origin.y + variant.height() > grid.height() {
+    return false;
+  }
+
+  variant.iter().all(|(offset, expected)| match expected {
+    Some(expected) => grid.get(Point::new(origin.x + offset.x, origin.y + offset.y)) == Some(expected),
+    None => true,
+  })
+}
+
+#[cfg(test)]
+mod pattern_template_tests {
+  use super::*;
+
+  #[test]
+  fn symmetric_shapes_only_have_distinct_variants() {
+    assert_eq!(PatternTemplate::parse(4, ".A.\nAAA\n.A.").unwrap().variants().len(), 1);
+    assert_eq!(PatternTemplate::parse(4, "M.S\n.A.\nM.S").unwrap().variants().len(), 4);
+    assert_eq!(PatternTemplate::parse(4, "X.\nX.\nXX").unwrap().variants().len(), 8);
+  }
+
+  #[test]
+  fn finds_plus_shapes() {
+    let grid = Grid::parse("BAB\nAAA\nBAB\nBBB");
+
+    assert_eq!(
+      PatternTemplate::parse(4, ".A.\nAAA\n.A.").unwrap().find(&grid),
+      vec![Placement { origin: Point::new(0, 0), variant: 0 }]
+    );
+  }
+
+  #[test]
+  fn finds_l_shapes_in_any_orientation() {
+    let grid = Grid::parse("LL.\nL..\nL..\n...");
+
+    let template = PatternTemplate::parse(4, "L.\nL.\nLL").unwrap();
+    let placements = template.find(&grid);
+
+    assert_eq!(placements.len(), 1);
+    assert_eq!(
+      template.positions(&placements[0]).collect::<Vec<_>>(),
+      vec![Point::new(0, 0), Point::new(1, 0), Point::new(0, 1), Point::new(0, 2)]
+    );
+  }
+
+  #[test]
+  fn ragged_and_wildcard_only_masks_are_errors() {
+    assert_eq!(
+      PatternTemplate::parse(4, "AB\nC").unwrap_err(),
+      ParseError::new(4, 2, 2, "C", ParseErrorKind::RaggedRow { expected: 2, found: 1 })
+    );
+    assert_eq!(PatternTemplate::parse(4, "..\n..").unwrap_err(), ParseError::new(4, 1, 1, "..\n..", ParseErrorKind::NoFixedCell));
+    assert_eq!(PatternTemplate::parse(4, "").unwrap_err().kind, ParseErrorKind::NoFixedCell);
+  }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use crate::grid::{Direction, Grid, Point};
+use crate::pattern::{PatternTemplate, Placement};
+use crate::word_search::WordMatch;
+
+const MASK: char = '.';
+const RESET: &str = "\x1b[0m";
+const PALETTE: [&str; 8] = ["31", "32", "33", "34", "35", "36", "91", "92"];
+
+// Cells that are not highlighted are replaced with `.`, like the dotted grids in the day4 puzzle text.
+// A highlight with a colour is wrapped in that ANSI SGR code.
+pub fn render_masked(grid: &Grid<char>, highlights: &HashMap<Point, Option<&str>>) -> String {
+  let mut output = String::with_capacity((grid.width() + 1) * grid.height());
+
+  for (point, cell) in grid.iter() {
+    match highlights.get(&point) {
+      Some(Some(colour)) => {
+        output.push_str("\x1b[");
+        output.push_str(colour);
+        output.push('m');
+        output.push(*cell);
+        output.push_str(RESET);
+      }
+      Some(None) => output.push(*cell),
+      None => output.push(MASK),
+    }
+
+    if point.x + 1 == grid.width() {
+      output.push('\n');
+    }
+  }
+
+  output
+}
+
+// Colours are per direction; where matches overlap the first one found wins
+pub fn render_word_matches(grid: &Grid<char>, matches: &[WordMatch], colour: bool) -> String {
+  let mut highlights = HashMap::new();
+  for word_match in matches {
+    let code = colour.then(|| direction_colour(word_match.direction));
+    for point in word_match.positions() {
+      highlights.entry(point).or_insert(code);
+    }
+  }
+
+  render_masked(grid, &highlights)
+}
+
+// Colours are per template variant; where placements overlap the first one found wins
+pub fn render_placements(grid: &Grid<char>, template: &PatternTemplate, placements: &[Placement], colour: bool) -> String {
+  let mut highlights = HashMap::new();
+  for placement in placements {
+    let code = colour.then(|| PALETTE[placement.variant % PALETTE.len()]);
+    for point in template.positions(placement) {
+      highlights.entry(point).or_insert(code);
+    }
+  }
+
+  render_masked(grid, &highlights)
+}
+
+fn direction_colour(direction: Direction) -> &'static str {
+  let index = Direction::ALL.iter().position(|d| *d == direction).unwrap_or(0);
+  PALETTE[index]
+}
+
+#[cfg(test)]
+mod render_tests {
+  use super::*;
+
+  #[test]
+  fn unhighlighted_cells_are_masked() {
+    let grid = Grid::parse("AB\nCD");
+    let highlights = HashMap::from([(Point::new(1, 0), None), (Point::new(0, 1), None)]);
+
+    assert_eq!(render_masked(&grid, &highlights), ".B\nC.\n");
+  }
+
+  #[test]
+  fn coloured_highlights_are_wrapped_in_ansi_codes() {
+    let grid = Grid::parse("AB");
+    let highlights = HashMap::from([(Point::new(0, 0), Some("31"))]);
+
+    assert_eq!(render_masked(&grid, &highlights), "\x1b[31mA\x1b[0m.\n");
+  }
+}
@@ -0,0 +1,48 @@
+use std::error::Error;
+use std::fmt;
+
+// An answer that doesn't fit in the solver's integer type. `context` names the sum or product that overflowed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overflow {
+  pub day: u8,
+  pub context: &'static str,
+}
+
+impl Overflow {
+  pub fn new(day: u8, context: &'static str) -> Overflow {
+    Overflow { day, context }
+  }
+}
+
+impl fmt::Display for Overflow {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "day {}: overflow in {}", self.day, self.context)
+  }
+}
+
+impl Error for Overflow {}
+
+// Stops at the first value that would take the total past i64::MAX or i64::MIN
+pub fn checked_sum(day: u8, context: &'static str, values: impl IntoIterator<Item = i64>) -> Result<i64, Overflow> {
+  values
+    .into_iter()
+    .try_fold(0i64, |total, value| total.checked_add(value))
+    .ok_or(Overflow::new(day, context))
+}
+
+#[cfg(test)]
+mod solve_error_tests {
+  use super::*;
+
+  #[test]
+  fn checked_sum_adds_past_i32() {
+    assert_eq!(checked_sum(3, "total", [i64::from(i32::MAX), 1]), Ok(2_147_483_648));
+  }
+
+  #[test]
+  fn checked_sum_reports_overflow() {
+    let error = checked_sum(5, "middle pages", [i64::MAX - 1, 1, 1]).unwrap_err();
+
+    assert_eq!(error.to_string(), "day 5: overflow in middle pages");
+  }
+}
@@ -0,0 +1,282 @@
+use std::any::Any;
+use std::error::Error;
+use std::rc::Rc;
+
+use crate::{day1, day2, day3, day4, day5};
+
+// A generator's output, whatever its type, so parts that share the generator can share it
+pub type Parsed = Rc<dyn Any>;
+
+// A part's answer formatted for printing
+pub type Answer = Result<String, Box<dyn Error>>;
+
+// A part solver, so the binary can run everything without cargo-aoc.
+// `name` matches the third argument of the solver's #[aoc] attribute. The day's #[aoc_generator] and the part
+// are called separately, so parsing and solving can be timed apart and parts can share one parsed input.
+// Parts without a generator read the raw input, so their timings don't include copying it.
+// Solvers that print a report instead of the puzzle's answer are not `verifiable` against the answers file.
+pub struct Solver {
+  pub day: u8,
+  pub part: u8,
+  pub name: &'static str,
+  pub verifiable: bool,
+  pub generator: Option<Generator>,
+  // Takes the raw input and the generator's output, `()` without a generator
+  pub runner: fn(&str, &dyn Any) -> Answer,
+}
+
+pub struct Generator {
+  // The generator's path, e.g. `day5::parse_input`. Solvers with the same one can share its output.
+  pub name: &'static str,
+  pub parse: fn(&str) -> Result<Parsed, Box<dyn Error>>,
+}
+
+impl Solver {
+  pub fn parse(&self, input: &str) -> Result<Parsed, Box<dyn Error>> {
+    match &self.generator {
+      Some(generator) => (generator.parse)(input),
+      None => Ok(Rc::new(())),
+    }
+  }
+
+  pub fn solve(&self, input: &str) -> Answer {
+    (self.runner)(input, &*self.parse(input)?)
+  }
+}
+
+// Generator outputs for one input per day, so the parts of a day that share a generator parse it only once
+#[derive(Default)]
+pub struct ParsedInputs {
+  parsed: Vec<(&'static str, Parsed)>,
+}
+
+impl ParsedInputs {
+  // The solver's parsed input, and whether this call had to run the generator for it
+  pub fn get_or_parse(&mut self, solver: &Solver, input: &str) -> Result<(Parsed, bool), Box<dyn Error>> {
+    let Some(generator) = &solver.generator else {
+      return Ok((solver.parse(input)?, false));
+    };
+    if let Some((_, parsed)) = self.parsed.iter().find(|(name, _)| *name == generator.name) {
+      return Ok((Rc::clone(parsed), false));
+    }
+    let parsed = solver.parse(input)?;
+    self.parsed.push((generator.name, Rc::clone(&parsed)));
+
+    Ok((parsed, true))
+  }
+}
+
+// Like aoc-runner, generators and parts that can't fail return their value directly instead of a Result
+trait IntoResult {
+  type Value;
+
+  fn into_result(self) -> Result<Self::Value, Box<dyn Error>>;
+}
+
+impl<T, E: Error + 'static> IntoResult for Result<T, E> {
+  type Value = T;
+
+  fn into_result(self) -> Result<T, Box<dyn Error>> {
+    Ok(self?)
+  }
+}
+
+impl IntoResult for day3::Program {
+  type Value = day3::Program;
+
+  fn into_result(self) -> Result<day3::Program, Box<dyn Error>> {
+    Ok(self)
+  }
+}
+
+impl IntoResult for String {
+  type Value = String;
+
+  fn into_result(self) -> Result<String, Box<dyn Error>> {
+    Ok(self)
+  }
+}
+
+impl IntoResult for u64 {
+  type Value = u64;
+
+  fn into_result(self) -> Result<u64, Box<dyn Error>> {
+    Ok(self)
+  }
+}
+
+// The generator is only passed in to name the type its output is downcast to
+fn generated<R: IntoResult>(_generator: fn(&str) -> R, parsed: &dyn Any) -> Result<&R::Value, Box<dyn Error>>
+where
+  R::Value: 'static,
+{
+  parsed.downcast_ref().ok_or_else(|| "parsed input is not the output of this solver's generator".into())
+}
+
+macro_rules! solver {
+  ($day:expr, $part:expr, $name:expr, $generator:path => $function:path) => {
+    solver!($day, $part, $name, $generator => $function, true)
+  };
+  ($day:expr, $part:expr, $name:expr, $generator:path => $function:path, report) => {
+    solver!($day, $part, $name, $generator => $function, false)
+  };
+  ($day:expr, $part:expr, $name:expr, $generator:path => $function:path, $verifiable:expr) => {
+    Solver {
+      day: $day,
+      part: $part,
+      name: $name,
+      verifiable: $verifiable,
+      generator: Some(Generator {
+        name: stringify!($generator),
+        parse: |input| Ok(Rc::new($generator(input).into_result()?)),
+      }),
+      runner: |_, parsed| Ok($function(generated($generator, parsed)?).into_result()?.to_string()),
+    }
+  };
+  ($day:expr, $part:expr, $name:expr, $function:path) => {
+    Solver {
+      day: $day,
+      part: $part,
+      name: $name,
+      verifiable: true,
+      generator: None,
+      runner: |input, _| Ok($function(input).into_result()?.to_string()),
+    }
+  };
+}
+
+// In day then part order, like the aoc-runner output. The first solver of a part is its reference variant,
+// the others are alternative implementations that cross-checking compares against it.
+pub const SOLVERS: &[Solver] = &[
+  solver!(1, 1, "Chars", day1::parse_location_lists => day1::part1),
+  solver!(1, 2, "Chars", day1::parse_location_lists => day1::part2),
+  solver!(2, 1, "Chars", day2::parse_reports => day2::part1),
+  solver!(2, 2, "Chars", day2::parse_reports => day2::part2),
+  solver!(2, 2, "BruteForce", day2::parse_reports => day2::part2_brute_force),
+  solver!(2, 2, "ToleranceCurve", day2::parse_reports => day2::part2_tolerance_curve, report),
+  solver!(3, 1, "Chars", day3::parse_program => day3::part1),
+  solver!(3, 1, "Regex", day3::part1_regex),
+  solver!(3, 2, "Chars", day3::parse_program => day3::part2),
+  solver!(3, 2, "Regex", day3::part2_regex),
+  solver!(4, 1, "Chars", day4::parse_grid => day4::part1),
+  solver!(4, 2, "Chars", day4::parse_grid => day4::part2),
+  solver!(5, 1, "Chars", day5::parse_input => day5::part1),
+  solver!(5, 2, "Chars", day5::parse_input => day5::part2),
+  solver!(5, 2, "SwapSort", day5::parse_input => day5::part2_swap_sort),
+];
+
+// The verifiable solvers of each selected part that has more than one, grouped by part in SOLVERS order
+pub fn variants(day: Option<u8>, part: Option<u8>) -> Vec<Vec<&'static Solver>> {
+  let mut groups: Vec<Vec<&'static Solver>> = Vec::new();
+  for solver in select(day, part).filter(|solver| solver.verifiable) {
+    match groups.last_mut() {
+      Some(group) if group[0].day == solver.day && group[0].part == solver.part => group.push(solver),
+      _ => groups.push(vec![solver]),
+    }
+  }
+  groups.retain(|group| group.len() > 1);
+
+  groups
+}
+
+// `None` matches every day or part
+pub fn select(day: Option<u8>, part: Option<u8>) -> impl Iterator<Item = &'static Solver> {
+  SOLVERS
+    .iter()
+    .filter(move |solver| day.is_none_or(|day| solver.day == day) && part.is_none_or(|part| solver.part == part))
+}
+
+#[cfg(test)]
+mod solvers_tests {
+  use super::*;
+
+  #[test]
+  fn select_filters_by_day_and_part() {
+    let names = select(Some(2), Some(2)).map(|solver| solver.name).collect::<Vec<_>>();
+
+    assert_eq!(names, vec!["Chars", "BruteForce", "ToleranceCurve"]);
+    assert_eq!(select(None, Some(1)).count(), 6);
+    assert_eq!(select(Some(6), None).count(), 0);
+  }
+
+  #[test]
+  fn variants_group_the_alternatives_of_each_part() {
+    let groups = variants(None, None)
+      .iter()
+      .map(|group| group.iter().map(|solver| (solver.day, solver.part, solver.name)).collect::<Vec<_>>())
+      .collect::<Vec<_>>();
+
+    assert_eq!(
+      groups,
+      vec![
+        vec![(2, 2, "Chars"), (2, 2, "BruteForce")],
+        vec![(3, 1, "Chars"), (3, 1, "Regex")],
+        vec![(3, 2, "Chars"), (3, 2, "Regex")],
+        vec![(5, 2, "Chars"), (5, 2, "SwapSort")],
+      ]
+    );
+    assert!(variants(Some(1), None).is_empty());
+  }
+
+  #[test]
+  fn solve_formats_the_answer() {
+    let solver = select(Some(3), Some(1)).next().unwrap();
+
+    assert_eq!(solver.solve("mul(2,4)mul(3,3)").unwrap(), "17");
+  }
+
+  #[test]
+  fn parts_of_a_day_share_one_parsed_input() {
+    let input = "7 6 4 2 1\n1 3 2 4 5";
+    let mut parsed_inputs = ParsedInputs::default();
+
+    let answers = select(Some(2), None)
+      .map(|solver| {
+        let (parsed, fresh) = parsed_inputs.get_or_parse(solver, input).unwrap();
+        ((solver.runner)(input, &*parsed).unwrap(), fresh)
+      })
+      .collect::<Vec<_>>();
+
+    assert_eq!(
+      answers,
+      vec![
+        (String::from("1"), true),
+        (String::from("2"), false),
+        (String::from("2"), false),
+        (String::from("k=0: 1, k=1: 2, k=2: 2, k=3: 2"), false),
+      ]
+    );
+    assert_eq!(parsed_inputs.parsed.len(), 1);
+  }
+
+  #[test]
+  fn runner_rejects_another_generators_output() {
+    let day1 = select(Some(1), Some(1)).next().unwrap();
+    let day2 = select(Some(2), Some(1)).next().unwrap();
+
+    let parsed = day2.parse("1 2 3").unwrap();
+
+    assert!((day1.runner)("1   2", &*parsed).is_err());
+  }
+
+  #[test]
+  fn solvers_without_a_generator_read_the_raw_input() {
+    let solver = select(Some(3), Some(1)).find(|solver| solver.name == "Regex").unwrap();
+    let mut parsed_inputs = ParsedInputs::default();
+
+    let (parsed, fresh) = parsed_inputs.get_or_parse(solver, "mul(2,4)mul(3,3)").unwrap();
+
+    assert_eq!((solver.runner)("mul(2,4)mul(3,3)", &*parsed).unwrap(), "17");
+    assert!(!fresh);
+    assert!(parsed_inputs.parsed.is_empty());
+  }
+
+  #[test]
+  fn solve_surfaces_errors() {
+    let solver = select(Some(1), Some(1)).next().unwrap();
+
+    let error = solver.solve("3 4").unwrap_err();
+
+    assert_eq!(error.to_string(), "day 1, line 1, column 1: expected `   ` in `3 4`");
+  }
+}
@@ -0,0 +1,31 @@
+// Left aligned columns separated by ` | `, one line per row with the header first
+pub fn format_table(header: &[&str], rows: &[Vec<String>]) -> String {
+  let mut widths = header.iter().map(|cell| cell.len()).collect::<Vec<_>>();
+  for row in rows {
+    for (width, cell) in widths.iter_mut().zip(row) {
+      *width = (*width).max(cell.chars().count());
+    }
+  }
+
+  let header = header.iter().map(|cell| cell.to_string()).collect::<Vec<_>>();
+  let mut table = String::new();
+  for row in std::iter::once(&header).chain(rows) {
+    let cells = row.iter().zip(&widths).map(|(cell, width)| format!("{:<width$}", cell, width = width)).collect::<Vec<_>>();
+    table.push_str(cells.join(" | ").trim_end());
+    table.push('\n');
+  }
+
+  table
+}
+
+#[cfg(test)]
+mod format_table_tests {
+  use super::*;
+
+  #[test]
+  fn pads_every_column_to_its_widest_cell() {
+    let rows = vec![vec![String::from("1"), String::from("1.2µs")], vec![String::from("10"), String::from("")]];
+
+    assert_eq!(format_table(&["day", "min"], &rows), "day | min\n1   | 1.2µs\n10  |\n");
+  }
+}
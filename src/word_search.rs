@@ -0,0 +1,109 @@
+use crate::grid::{Direction, Grid, Point};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WordMatch<'a> {
+  pub word: &'a str,
+  pub start: Point,
+  pub direction: Direction,
+}
+
+impl WordMatch<'_> {
+  // The grid cells covered by the match, starting with `start`
+  pub fn positions(&self) -> impl Iterator<Item = Point> + '_ {
+    let (dx, dy) = self.direction.offset();
+    (0..self.word.chars().count() as isize).map(move |i| {
+      Point::new(self.start.x.wrapping_add_signed(dx * i), self.start.y.wrapping_add_signed(dy * i))
+    })
+  }
+}
+
+pub struct WordSearch<'a> {
+  words: Vec<(&'a str, Vec<char>)>,
+  directions: Vec<Direction>,
+}
+
+impl<'a> WordSearch<'a> {
+  pub fn new(words: &[&'a str]) -> WordSearch<'a> {
+    WordSearch {
+      words: words.iter().map(|word| (*word, word.chars().collect())).collect(),
+      directions: Direction::ALL.to_vec(),
+    }
+  }
+
+  pub fn directions(mut self, directions: &[Direction]) -> WordSearch<'a> {
+    self.directions = directions.to_vec();
+    self
+  }
+
+  // Matches are ordered by start position (row by row), then word, then direction
+  pub fn find(&self, grid: &Grid<char>) -> Vec<WordMatch<'a>> {
+    let mut matches = Vec::new();
+
+    for (start, first_char) in grid.iter() {
+      for (word, chars) in &self.words {
+        if chars.first() != Some(first_char) {
+          continue;
+        }
+
+        for direction in &self.directions {
+          let mut ray = grid.ray(start, *direction);
+          if chars.iter().all(|c| ray.next().is_some_and(|(_, cell)| cell == c)) {
+            matches.push(WordMatch {
+              word,
+              start,
+              direction: *direction,
+            });
+          }
+        }
+      }
+    }
+
+    matches
+  }
+
+  pub fn count(&self, grid: &Grid<char>) -> usize {
+    self.find(grid).len()
+  }
+}
+
+#[cfg(test)]
+mod word_search_tests {
+  use super::*;
+
+  #[test]
+  fn finds_every_direction_with_positions() {
+    let grid = Grid::parse("CAT\nA.A\nTAC");
+
+    let matches = WordSearch::new(&["CAT"]).find(&grid);
+
+    assert_eq!(
+      matches,
+      vec![
+        WordMatch { word: "CAT", start: Point::new(0, 0), direction: Direction::East },
+        WordMatch { word: "CAT", start: Point::new(0, 0), direction: Direction::South },
+        WordMatch { word: "CAT", start: Point::new(2, 2), direction: Direction::North },
+        WordMatch { word: "CAT", start: Point::new(2, 2), direction: Direction::West },
+      ]
+    );
+    assert_eq!(
+      matches[3].positions().collect::<Vec<_>>(),
+      vec![Point::new(2, 2), Point::new(1, 2), Point::new(0, 2)]
+    );
+  }
+
+  #[test]
+  fn searches_for_several_words_at_once() {
+    let grid = Grid::parse("DOGCAT");
+
+    let words = WordSearch::new(&["DOG", "CAT", "GOD"]).find(&grid).iter().map(|m| m.word).collect::<Vec<_>>();
+
+    assert_eq!(words, vec!["DOG", "GOD", "CAT"]);
+  }
+
+  #[test]
+  fn words_running_off_the_edge_do_not_match() {
+    let grid = Grid::parse("XMA\nMMM\nAAA");
+
+    assert_eq!(WordSearch::new(&["XMAS"]).count(&grid), 0);
+  }
+}